@@ -0,0 +1,48 @@
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+// Written first so that a new release, which may have new fields, or a
+// changed config, which may turn fields on, probes again
+fn header(config_key: u64) -> String {
+    format!("# risifetch {} {:016x}", env!("CARGO_PKG_VERSION"), config_key)
+}
+
+// $XDG_CACHE_HOME/risifetch/fields, or ~/.cache/risifetch/fields
+fn fields_path() -> Option<PathBuf> {
+    let cache_home = env::var_os("XDG_CACHE_HOME")
+        .map(PathBuf::from)
+        .filter(|path| path.is_absolute())
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".cache")))?;
+
+    Some(cache_home.join("risifetch/fields"))
+}
+
+// The fields found available on an earlier run with the same config
+pub fn load_fields(config_key: u64) -> Option<Vec<String>> {
+    let contents = fs::read_to_string(fields_path()?).ok()?;
+    let mut lines = contents.lines();
+
+    if lines.next() != Some(header(config_key).as_str()) {
+        return None;
+    }
+    Some(lines.map(str::to_string).collect())
+}
+
+// Failing to write the cache only means probing again next time
+pub fn save_fields(config_key: u64, names: &[&str]) {
+    let Some(path) = fields_path() else {
+        return;
+    };
+
+    let mut contents = format!("{}\n", header(config_key));
+    for name in names {
+        contents += name;
+        contents += "\n";
+    }
+
+    if let Some(dir) = path.parent() {
+        let _ = fs::create_dir_all(dir);
+    }
+    let _ = fs::write(path, contents);
+}
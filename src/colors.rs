@@ -1,21 +1,241 @@
-pub mod codes {
-    pub const _RED: &str = "\x1b[31m";
-    pub const _GREEN: &str = "\x1b[32m";
-    pub const _YELLOW: &str = "\x1b[33m";
-    pub const _BLUE: &str = "\x1b[34m";
-    pub const _CYAN: &str = "\x1b[36m";
-    pub const _GRAY: &str = "\x1b[38;5;8m";
-    pub const _MAGENTA: &str = "\x1b[35m";
-    pub const _BOLD: &str = "\x1b[1m";
-    pub const _RESET: &str = "\x1b[0m";
-}
-
-pub use self::codes::_RED as red;
-pub use self::codes::_GREEN as green;
-pub use self::codes::_YELLOW as yellow;
-pub use self::codes::_BLUE as blue;
-pub use self::codes::_CYAN as cyan;
-pub use self::codes::_MAGENTA as magenta;
-pub use self::codes::_GRAY as gray;
-pub use self::codes::_BOLD as bold;
-pub use self::codes::_RESET as reset;
+use std::env;
+use std::str::FromStr;
+use std::sync::OnceLock;
+use serde::Deserialize;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "String")]
+pub enum Color {
+    Red,
+    Green,
+    Yellow,
+    Blue,
+    Magenta,
+    Cyan,
+    Gray,
+    White,
+    Rgb(u8, u8, u8),
+}
+
+// How many colors the terminal can show, Plain means no escapes at all
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ColorMode {
+    Plain,
+    Ansi16,
+    Ansi256,
+    TrueColor,
+}
+
+impl ColorMode {
+    pub fn name(self) -> &'static str {
+        match self {
+            ColorMode::Plain => "none",
+            ColorMode::Ansi16 => "16",
+            ColorMode::Ansi256 => "256",
+            ColorMode::TrueColor => "truecolor",
+        }
+    }
+}
+
+// Rough RGB values of the basic colors, used to pick the closest one
+const ANSI16_PALETTE: [(u8, u8, u8, u8); 8] = [
+    (0, 0, 0, 30),
+    (205, 0, 0, 31),
+    (0, 205, 0, 32),
+    (205, 205, 0, 33),
+    (0, 0, 238, 34),
+    (205, 0, 205, 35),
+    (0, 205, 205, 36),
+    (229, 229, 229, 37),
+];
+
+static COLOR_MODE: OnceLock<ColorMode> = OnceLock::new();
+
+impl Color {
+    // The escape sequence that switches the terminal to this color
+    pub fn escape(self, mode: ColorMode) -> String {
+        if mode == ColorMode::Plain {
+            return String::new();
+        }
+
+        match self {
+            Color::Red => "\x1b[31m".to_string(),
+            Color::Green => "\x1b[32m".to_string(),
+            Color::Yellow => "\x1b[33m".to_string(),
+            Color::Blue => "\x1b[34m".to_string(),
+            Color::Magenta => "\x1b[35m".to_string(),
+            Color::Cyan => "\x1b[36m".to_string(),
+            Color::Gray if mode == ColorMode::Ansi16 => "\x1b[90m".to_string(),
+            Color::Gray => "\x1b[38;5;8m".to_string(),
+            Color::White => "\x1b[37m".to_string(),
+            Color::Rgb(r, g, b) => match mode {
+                ColorMode::TrueColor => format!("\x1b[38;2;{};{};{}m", r, g, b),
+                ColorMode::Ansi256 => format!("\x1b[38;5;{}m", rgb_to_256(r, g, b)),
+                _ => format!("\x1b[{}m", rgb_to_16(r, g, b)),
+            },
+        }
+    }
+}
+
+// Closest entry of the 6x6x6 color cube or the gray ramp
+fn rgb_to_256(r: u8, g: u8, b: u8) -> u8 {
+    if r == g && g == b {
+        return match r {
+            0..=7 => 16,
+            248..=255 => 231,
+            _ => 232 + (r - 8) / 10,
+        };
+    }
+
+    let level = |channel: u8| ((u16::from(channel) * 5 + 127) / 255) as u8;
+
+    16 + 36 * level(r) + 6 * level(g) + level(b)
+}
+
+// SGR code of the basic color nearest to the given one
+fn rgb_to_16(r: u8, g: u8, b: u8) -> u8 {
+    let distance = |(pr, pg, pb, _): &(u8, u8, u8, u8)| {
+        let d = |a: u8, b: u8| (i32::from(a) - i32::from(b)).pow(2);
+        d(r, *pr) + d(g, *pg) + d(b, *pb)
+    };
+
+    ANSI16_PALETTE.iter().min_by_key(|entry| distance(entry)).map_or(37, |entry| entry.3)
+}
+
+// Names as used in the config file, e.g. "cyan" or "#ff79c6"
+impl FromStr for Color {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        let color = match name.to_lowercase().as_str() {
+            "red" => Color::Red,
+            "green" => Color::Green,
+            "yellow" => Color::Yellow,
+            "blue" => Color::Blue,
+            "magenta" => Color::Magenta,
+            "cyan" => Color::Cyan,
+            "gray" | "grey" => Color::Gray,
+            "white" => Color::White,
+            hex if hex.len() == 7 && hex.starts_with('#') => {
+                let channel = |range| u8::from_str_radix(&hex[range], 16)
+                    .map_err(|_| format!("invalid color '{}'", name));
+                Color::Rgb(channel(1..3)?, channel(3..5)?, channel(5..7)?)
+            }
+            _ => return Err(format!("unknown color '{}'", name)),
+        };
+
+        Ok(color)
+    }
+}
+
+impl TryFrom<String> for Color {
+    type Error = String;
+
+    fn try_from(name: String) -> Result<Self, Self::Error> {
+        name.parse()
+    }
+}
+
+pub fn bold(mode: ColorMode) -> &'static str {
+    match mode {
+        ColorMode::Plain => "",
+        _ => "\x1b[1m",
+    }
+}
+
+pub fn reset(mode: ColorMode) -> &'static str {
+    match mode {
+        ColorMode::Plain => "",
+        _ => "\x1b[0m",
+    }
+}
+
+// Decide on colors from the environment. CLICOLOR_FORCE turns them on even
+// for pipes, then https://no-color.org and CLICOLOR=0 turn them off, then
+// pipes don't get littered with escape codes. What the terminal supports is
+// guessed from $COLORTERM and $TERM.
+fn resolve_mode(var: impl Fn(&str) -> Option<String>, is_tty: bool) -> ColorMode {
+    let set = |name: &str| var(name).is_some_and(|value| !value.is_empty());
+    let force = var("CLICOLOR_FORCE").is_some_and(|value| !value.is_empty() && value != "0");
+    let colorterm = var("COLORTERM").unwrap_or_default();
+    let term = var("TERM").unwrap_or_default();
+
+    if !force && (set("NO_COLOR") || var("CLICOLOR").as_deref() == Some("0") || !is_tty) {
+        return ColorMode::Plain;
+    }
+
+    if term == "dumb" && !force {
+        ColorMode::Plain
+    } else if colorterm == "truecolor" || colorterm == "24bit" || term.ends_with("-direct") {
+        ColorMode::TrueColor
+    } else if term.contains("256color") {
+        ColorMode::Ansi256
+    } else {
+        ColorMode::Ansi16
+    }
+}
+
+// Colors for stdout, or for the file written with --output when `to_file`
+pub fn detect_mode(to_file: bool) -> ColorMode {
+    detect_mode_with(|name| env::var(name).ok(), to_file, nix::unistd::isatty(1).unwrap_or(false))
+}
+
+// A file is no terminal, even when risifetch runs in one
+fn detect_mode_with(var: impl Fn(&str) -> Option<String>, to_file: bool, stdout_is_tty: bool) -> ColorMode {
+    resolve_mode(var, stdout_is_tty && !to_file)
+}
+
+// The mode is detected once and shared by everything that prints
+pub fn mode() -> ColorMode {
+    *COLOR_MODE.get_or_init(|| detect_mode(false))
+}
+
+// Override detection, only has an effect before the first call to mode()
+pub fn set_mode(mode: ColorMode) {
+    let _ = COLOR_MODE.set(mode);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mode_with(vars: &[(&str, &str)], is_tty: bool) -> ColorMode {
+        let var = |name: &str| vars.iter()
+            .find(|(var, _)| *var == name)
+            .map(|(_, value)| value.to_string());
+
+        resolve_mode(var, is_tty)
+    }
+
+    #[test]
+    fn clicolor_force_wins() {
+        assert_eq!(mode_with(&[("CLICOLOR_FORCE", "1")], false), ColorMode::Ansi16);
+        assert_eq!(mode_with(&[("CLICOLOR_FORCE", "1"), ("NO_COLOR", "1")], true), ColorMode::Ansi16);
+        assert_eq!(mode_with(&[("CLICOLOR_FORCE", "1"), ("CLICOLOR", "0")], true), ColorMode::Ansi16);
+        assert_eq!(mode_with(&[("CLICOLOR_FORCE", "0")], false), ColorMode::Plain);
+    }
+
+    #[test]
+    fn no_color_and_clicolor_turn_colors_off() {
+        assert_eq!(mode_with(&[("NO_COLOR", "1")], true), ColorMode::Plain);
+        assert_eq!(mode_with(&[("CLICOLOR", "0")], true), ColorMode::Plain);
+        assert_eq!(mode_with(&[("NO_COLOR", "")], true), ColorMode::Ansi16);
+        assert_eq!(mode_with(&[("CLICOLOR", "1")], true), ColorMode::Ansi16);
+    }
+
+    #[test]
+    fn colors_only_on_terminals() {
+        assert_eq!(mode_with(&[("CLICOLOR", "1")], false), ColorMode::Plain);
+        assert_eq!(mode_with(&[("COLORTERM", "truecolor")], true), ColorMode::TrueColor);
+    }
+
+    #[test]
+    fn output_files_get_colors_only_when_forced() {
+        let no_vars = |_: &str| None;
+        let force = |name: &str| (name == "CLICOLOR_FORCE").then(|| "1".to_string());
+
+        assert_eq!(detect_mode_with(no_vars, true, true), ColorMode::Plain);
+        assert_eq!(detect_mode_with(no_vars, false, true), ColorMode::Ansi16);
+        assert_eq!(detect_mode_with(force, true, true), ColorMode::Ansi16);
+    }
+}
@@ -0,0 +1,584 @@
+use std::collections::BTreeMap;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::env;
+use std::fs;
+use std::io::{self, ErrorKind};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use serde::Deserialize;
+use crate::colors::Color;
+use crate::error::ConfigError;
+use crate::packages;
+
+// Settings read from config.toml, every key is optional
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    // Leave out the fields that would use the network, like --offline
+    pub offline: bool,
+    pub theme: Option<String>,
+    // Colors that replace the theme's
+    pub colors: ThemeColors,
+    // Fields to show next to the logo, by default those that were available
+    // the first time risifetch ran
+    pub fields: Option<Vec<String>>,
+    // What to do with an OS name too long to fit next to the logo
+    pub os_overflow: Option<Overflow>,
+    // Show os-release's NAME instead of PRETTY_NAME
+    pub os_short_name: bool,
+    // Show only the kernel's version, without the build suffix
+    pub kernel_short: bool,
+    // Show cached and buffer memory next to the used figure
+    pub memory_detail: bool,
+    // Say when swap is zram, which is compressed RAM and not disk
+    pub swap_zram: bool,
+    pub memory_used: MemoryUsed,
+    pub uptime_format: UptimeFormat,
+    pub temp_unit: TempUnit,
+    // Write numbers with the locale's separators instead of the C locale's
+    pub locale_numbers: bool,
+    // Network interface for the network fields, instead of the default route's
+    pub interface: Option<String>,
+    pub ip_family: IpFamily,
+    pub disk: DiskConfig,
+    pub usage: UsageConfig,
+    pub precision: PrecisionConfig,
+    pub battery: BatteryConfig,
+    // Logo to show with --small when the distro has no compact one
+    pub small_fallback: SmallFallback,
+    // How long the programs fields run may take, 1000 by default
+    pub command_timeout_ms: Option<u64>,
+    pub oneline: OnelineConfig,
+    pub private: PrivateConfig,
+    pub header: HeaderConfig,
+    pub labels: LabelConfig,
+    pub logo: LogoConfig,
+    pub layout: LayoutConfig,
+    pub cpu: CpuConfig,
+    pub gpu: GpuConfig,
+    pub packages: PackagesConfig,
+    pub terminal: TerminalConfig,
+    pub shell: ShellConfig,
+    // Shown for listed fields that have no value, instead of leaving them out
+    pub placeholder: Option<String>,
+    // Ditto for single fields, wins over `placeholder`
+    pub placeholders: BTreeMap<String, String>,
+    // Kernel tunables under /proc/sys to show after the built-in fields, e.g.
+    // "vm.swappiness"
+    pub sysctl: Vec<String>,
+    // Extra fields filled in by shell commands, shown after the built-in ones
+    pub custom: Vec<CustomField>,
+    // Named sets of overrides picked with --profile
+    pub profiles: BTreeMap<String, Profile>,
+}
+
+// What a profile can override, anything it leaves out keeps the base value
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Profile {
+    pub theme: Option<String>,
+    pub fields: Option<Vec<String>>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CustomField {
+    pub key: String,
+    // Run with `sh -c`, its trimmed output becomes the value
+    pub command: String,
+    // Instead of command_timeout_ms
+    #[serde(default)]
+    pub timeout_ms: Option<u64>,
+    // Skipped when offline
+    #[serde(default)]
+    pub network: bool,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ShellConfig {
+    pub source: ShellSource,
+}
+
+// Which shell the shell field shows
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ShellSource {
+    // $SHELL, the login shell
+    #[default]
+    Login,
+    // The one risifetch was started from
+    Running,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TerminalConfig {
+    // Process names of terminals missing from the built-in list
+    pub known: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PackagesConfig {
+    // Package managers to count, see packages::MANAGERS
+    pub managers: Vec<String>,
+    // Look for pending updates, which is slow and may need the network
+    pub updates: bool,
+    // How long the update check may take, instead of command_timeout_ms
+    pub updates_timeout_ms: Option<u64>,
+}
+
+impl Default for PackagesConfig {
+    fn default() -> Self {
+        PackagesConfig {
+            managers: packages::MANAGERS.iter().map(|manager| manager.to_string()).collect(),
+            updates: false,
+            updates_timeout_ms: None,
+        }
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct GpuConfig {
+    // Fill in the vulkan field, which means running vulkaninfo
+    pub vulkan: bool,
+    pub multiple: GpuMultiple,
+}
+
+// How the gpu field shows several cards
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GpuMultiple {
+    // All on one line, separated by commas
+    #[default]
+    Join,
+    // One numbered line per card
+    List,
+    // Identical cards counted, e.g. "2x NVIDIA GeForce RTX 3090"
+    Collapse,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CpuConfig {
+    // Show the model name exactly as /proc/cpuinfo has it
+    pub raw_name: bool,
+    // Fill in the cores field, which means waiting a moment for /proc/stat
+    pub cores: bool,
+}
+
+// Where the info block sits next to the logo
+#[derive(Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LayoutConfig {
+    pub values: ValueAlign,
+    pub vertical: VerticalAlign,
+    // Blank columns between the logo and the info block
+    pub gap: usize,
+    // Blank lines above and below everything
+    pub lines_before: usize,
+    pub lines_after: usize,
+}
+
+impl Default for LayoutConfig {
+    fn default() -> Self {
+        LayoutConfig {
+            values: ValueAlign::default(),
+            vertical: VerticalAlign::default(),
+            gap: 1,
+            lines_before: 1,
+            lines_after: 1,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ValueAlign {
+    // Right after the label
+    #[default]
+    Left,
+    // Flush with the end of the longest value
+    Right,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum VerticalAlign {
+    #[default]
+    Top,
+    // Halfway down the logo
+    Center,
+    // Ending with the logo's last line
+    Bottom,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LogoConfig {
+    // Replacement colors per logo, keyed by the distro ID
+    pub colors: BTreeMap<String, Vec<Color>>,
+    // Draw the bullets and names in the logo's primary color instead of the
+    // theme's accent
+    pub accent_from_logo: bool,
+}
+
+// How the field keys are drawn
+#[derive(Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LabelConfig {
+    // In front of every key, may be empty
+    pub bullet: String,
+    pub case: LabelCase,
+    // Labels shown instead of the field names, as they're written
+    pub names: BTreeMap<String, String>,
+}
+
+impl Default for LabelConfig {
+    fn default() -> Self {
+        LabelConfig {
+            bullet: "▪".to_string(),
+            case: LabelCase::default(),
+            names: BTreeMap::new(),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LabelCase {
+    // As the fields are named, e.g. "kernel"
+    #[default]
+    Lower,
+    // e.g. "KERNEL"
+    Upper,
+    // e.g. "Kernel"
+    Title,
+}
+
+// Overrides for single colors of the theme, the rest come from the theme
+#[derive(Clone, Copy, Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ThemeColors {
+    pub label: Option<Color>,
+    pub value: Option<Color>,
+    pub accent: Option<Color>,
+    pub separator: Option<Color>,
+}
+
+// The user@host line and the rule drawn under it
+#[derive(Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct HeaderConfig {
+    // Draw user@host at all
+    pub show: bool,
+    // Repeated to underline user@host, an empty string drops the line
+    pub separator: String,
+    // How far the line under user@host goes
+    pub separator_width: SeparatorWidth,
+    // Defaults to the theme's separator color
+    pub separator_color: Option<Color>,
+    // Default to the theme's accent, value and accent colors
+    pub username_color: Option<Color>,
+    pub at_color: Option<Color>,
+    pub hostname_color: Option<Color>,
+}
+
+impl Default for HeaderConfig {
+    fn default() -> Self {
+        HeaderConfig {
+            show: true,
+            separator: "-".to_string(),
+            separator_width: SeparatorWidth::default(),
+            separator_color: None,
+            username_color: None,
+            at_color: None,
+            hostname_color: None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SeparatorWidth {
+    // As wide as user@host
+    #[default]
+    Header,
+    // As wide as the widest field
+    Info,
+    // Up to the terminal's right edge
+    Terminal,
+}
+
+// What --private hides besides the hostname
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PrivateConfig {
+    pub hide_username: bool,
+}
+
+// What --oneline shows after user@host
+#[derive(Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct OnelineConfig {
+    pub fields: Vec<String>,
+    pub separator: String,
+}
+
+impl Default for OnelineConfig {
+    fn default() -> Self {
+        OnelineConfig {
+            fields: vec!["os".to_string(), "kernel".to_string(), "uptime".to_string()],
+            separator: " | ".to_string(),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum UptimeFormat {
+    // e.g. "2d 3h 5m"
+    #[default]
+    Compact,
+    // e.g. "2 days, 3 hours, 5 minutes"
+    Pretty,
+}
+
+// What counts as used memory
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MemoryUsed {
+    // Total minus MemAvailable
+    #[default]
+    Available,
+    // Total minus MemFree
+    Free,
+}
+
+// Which addresses the ip field shows
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum IpFamily {
+    #[default]
+    Ipv4,
+    Ipv6,
+    Both,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+pub enum TempUnit {
+    #[default]
+    #[serde(rename = "C")]
+    Celsius,
+    #[serde(rename = "F")]
+    Fahrenheit,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SmallFallback {
+    #[default]
+    Full,
+    None,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DiskConfig {
+    // Usage percentage above which the figure is drawn in warn_color
+    pub warn_at: Option<u8>,
+    pub warn_color: Color,
+    // Add a line with the inode usage
+    pub inodes: bool,
+}
+
+impl Default for DiskConfig {
+    fn default() -> Self {
+        DiskConfig {
+            warn_at: None,
+            warn_color: Color::Red,
+            inodes: false,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct BatteryConfig {
+    // Follow the percentage with the estimated time left
+    pub remaining_time: bool,
+}
+
+impl Default for BatteryConfig {
+    fn default() -> Self {
+        BatteryConfig {
+            remaining_time: true,
+        }
+    }
+}
+
+// Decimal places of numeric fields
+#[derive(Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PrecisionConfig {
+    pub load: usize,
+    pub temp: usize,
+}
+
+impl Default for PrecisionConfig {
+    fn default() -> Self {
+        PrecisionConfig {
+            load: 2,
+            temp: 0,
+        }
+    }
+}
+
+// Coloring memory, swap and disk by how full they are
+#[derive(Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct UsageConfig {
+    // Off by default, the values then keep the theme's value color
+    pub colors: bool,
+    // Usage percentages from which the value turns yellow and then red
+    pub yellow_at: u8,
+    pub red_at: u8,
+}
+
+impl Default for UsageConfig {
+    fn default() -> Self {
+        UsageConfig {
+            colors: false,
+            yellow_at: 50,
+            red_at: 85,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Overflow {
+    Truncate,
+    Wrap,
+}
+
+impl Config {
+    // What to show for a listed field without a value, if anything
+    pub fn placeholder(&self, field: &str) -> Option<&str> {
+        self.placeholders.get(field).or(self.placeholder.as_ref()).map(String::as_str)
+    }
+
+    // Layer the named profile over the base settings
+    pub fn apply_profile(&mut self, name: &str) -> Result<(), ConfigError> {
+        let profile = self.profiles.get(name)
+            .ok_or_else(|| ConfigError::UnknownProfile(name.to_string()))?;
+
+        if let Some(theme) = &profile.theme {
+            self.theme = Some(theme.clone());
+        }
+        if let Some(fields) = &profile.fields {
+            self.fields = Some(fields.clone());
+        }
+
+        Ok(())
+    }
+
+    pub fn command_timeout(&self) -> Duration {
+        Duration::from_millis(self.command_timeout_ms.unwrap_or(1000))
+    }
+
+    // A timeout of a custom field or the update check, which falls back to
+    // the one for every command
+    pub fn timeout_or_default(&self, timeout_ms: Option<u64>) -> Duration {
+        timeout_ms.map_or_else(|| self.command_timeout(), Duration::from_millis)
+    }
+
+    // Changes whenever a setting does, for telling cached results apart
+    pub fn key(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        format!("{:?}", self).hash(&mut hasher);
+        hasher.finish()
+    }
+}
+
+// Config files in the order they're tried, following the XDG base directory
+// spec
+fn config_paths() -> Vec<PathBuf> {
+    let mut paths = Vec::new();
+
+    // Relative values are invalid per the spec and must be ignored
+    if let Some(config_home) = env::var_os("XDG_CONFIG_HOME").map(PathBuf::from) {
+        if config_home.is_absolute() {
+            paths.push(config_home.join("risifetch/config.toml"));
+        }
+    }
+    if let Some(home) = env::var_os("HOME") {
+        paths.push(PathBuf::from(home).join(".config/risifetch/config.toml"));
+    }
+    paths.push(PathBuf::from("/etc/risifetch/config.toml"));
+
+    paths
+}
+
+// "-" reads standard input, for configs generated on the fly by scripts
+fn read(path: PathBuf) -> Result<Config, ConfigError> {
+    let (path, contents) = if path == Path::new("-") {
+        (PathBuf::from("<stdin>"), io::read_to_string(io::stdin()))
+    } else {
+        let contents = fs::read_to_string(&path);
+        (path, contents)
+    };
+    let contents = contents.map_err(|err| ConfigError::Io(path.clone(), err))?;
+
+    toml::from_str(&contents).map_err(|err| ConfigError::Parse(path, err))
+}
+
+// Load the file given with --config, or the first config file that exists.
+// No config file at all just means the defaults are used.
+pub fn load(path: Option<PathBuf>) -> Result<Config, ConfigError> {
+    if let Some(path) = path {
+        return read(path);
+    }
+
+    for path in config_paths() {
+        match fs::metadata(&path) {
+            Ok(_) => return read(path),
+            Err(err) if err.kind() == ErrorKind::NotFound => continue,
+            Err(err) => return Err(ConfigError::Io(path, err)),
+        }
+    }
+
+    Ok(Config::default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn timeouts_fall_back_to_command_timeout() {
+        let config: Config = toml::from_str(r#"
+            command_timeout_ms = 3000
+
+            [packages]
+            updates_timeout_ms = 5000
+
+            [[custom]]
+            key = "slow"
+            command = "sleep 1"
+
+            [[custom]]
+            key = "quick"
+            command = "true"
+            timeout_ms = 200
+        "#).unwrap();
+
+        assert_eq!(config.timeout_or_default(config.packages.updates_timeout_ms), Duration::from_millis(5000));
+        assert_eq!(config.timeout_or_default(config.custom[0].timeout_ms), Duration::from_millis(3000));
+        assert_eq!(config.timeout_or_default(config.custom[1].timeout_ms), Duration::from_millis(200));
+        assert_eq!(Config::default().timeout_or_default(None), Duration::from_millis(1000));
+    }
+}
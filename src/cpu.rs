@@ -0,0 +1,229 @@
+use std::thread;
+use std::time::Duration;
+use regex::Regex;
+use crate::config::TempUnit;
+use crate::error::FetchError;
+use crate::numbers;
+use crate::system::SystemInfo;
+
+// Marketing noise in /proc/cpuinfo model names, removed in order
+const NOISE: [&str; 7] = [
+    r"(?i)\((r|tm)\)|[®™]",
+    r"(?i)\b\d+(st|nd|rd|th) gen\b",
+    r"@ *[\d.]+ *[GM]Hz",
+    r"(?i)\bwith radeon( \w+)? graphics\b",
+    r"(?i)\b\d+-core\b",
+    r"(?i)\b(cpu|processor)\b",
+    r"\s+",
+];
+
+// The model name as /proc/cpuinfo reports it, e.g.
+// "11th Gen Intel(R) Core(TM) i7-1165G7 @ 2.80GHz"
+fn model_name(sys: &dyn SystemInfo) -> Result<String, FetchError> {
+    let cpuinfo = sys.read_required("/proc/cpuinfo")?;
+
+    cpuinfo.lines()
+        .find(|line| line.starts_with("model name"))
+        .and_then(|line| line.split_once(':'))
+        .map(|(_, name)| name.trim().to_string())
+        .filter(|name| !name.is_empty())
+        .ok_or_else(|| FetchError::NotAvailable("cpu model name".to_string()))
+}
+
+// e.g. "Intel Core i7-1165G7" or "AMD Ryzen 5 5600X"
+fn normalize(name: &str) -> String {
+    let mut name = name.to_string();
+
+    for pattern in NOISE {
+        let re = Regex::new(pattern).unwrap();
+        name = re.replace_all(&name, " ").into_owned();
+    }
+
+    name.trim().to_string()
+}
+
+// Names that are nothing but noise, e.g. "Processor", are shown as they are
+pub fn get_cpu(sys: &dyn SystemInfo, raw_name: bool) -> Result<String, FetchError> {
+    let name = model_name(sys)?;
+    if raw_name {
+        return Ok(name);
+    }
+
+    let normalized = normalize(&name);
+    Ok(if normalized.is_empty() { name } else { normalized })
+}
+
+// Cache sizes per level of the first core, e.g. "L1 64K, L2 512K, L3 32M",
+// with L1's data and instruction caches added up
+pub fn get_cpu_cache(sys: &dyn SystemInfo) -> Result<String, FetchError> {
+    const CACHE: &str = "/sys/devices/system/cpu/cpu0/cache";
+
+    let unavailable = || FetchError::NotAvailable("cpu cache".to_string());
+    let caches = sys.read_dir(CACHE).map_err(|_| unavailable())?;
+
+    // KiB per level, in level order
+    let mut levels: Vec<(u32, u64)> = Vec::new();
+    for cache in caches.iter().filter(|name| name.starts_with("index")) {
+        let read = |file: &str| sys.read_file(&format!("{}/{}/{}", CACHE, cache, file)).ok();
+        let Some(level) = read("level").and_then(|level| level.trim().parse::<u32>().ok()) else {
+            continue;
+        };
+        // e.g. "32K"
+        let Some(size) = read("size").and_then(|size| size.trim().trim_end_matches('K').parse::<u64>().ok()) else {
+            continue;
+        };
+
+        match levels.iter_mut().find(|(seen, _)| *seen == level) {
+            Some((_, total)) => *total += size,
+            None => levels.push((level, size)),
+        }
+    }
+
+    if levels.is_empty() {
+        return Err(unavailable());
+    }
+    levels.sort();
+
+    Ok(levels.iter()
+        .map(|(level, kib)| match kib {
+            kib if kib % 1024 == 0 => format!("L{} {}M", level, kib / 1024),
+            kib => format!("L{} {}K", level, kib),
+        })
+        .collect::<Vec<_>>()
+        .join(", "))
+}
+
+// hwmon drivers that report the CPU package temperature
+const CPU_SENSORS: [&str; 4] = ["coretemp", "k10temp", "zenpower", "cpu_thermal"];
+
+// CPU temperature in °C, from hwmon's temp1_input in millidegrees
+fn read_temperature(sys: &dyn SystemInfo) -> Option<f64> {
+    const HWMON: &str = "/sys/class/hwmon";
+
+    let mut sensors = sys.read_dir(HWMON).ok()?;
+    sensors.sort();
+
+    sensors.iter().find_map(|sensor| {
+        let dir = format!("{}/{}", HWMON, sensor);
+        let name = sys.read_file(&format!("{}/name", dir)).ok()?;
+        if !CPU_SENSORS.contains(&name.trim()) {
+            return None;
+        }
+
+        let millidegrees: f64 = sys.read_file(&format!("{}/temp1_input", dir)).ok()?.trim().parse().ok()?;
+        Some(millidegrees / 1000.0)
+    })
+}
+
+pub fn get_temperature(sys: &dyn SystemInfo, unit: TempUnit, decimals: usize) -> Result<String, FetchError> {
+    let celsius = read_temperature(sys)
+        .ok_or_else(|| FetchError::NotAvailable("cpu temperature".to_string()))?;
+
+    Ok(format_temperature(celsius, unit, decimals))
+}
+
+// Every temperature goes through here so they all honor temp_unit and the
+// configured precision
+pub fn format_temperature(celsius: f64, unit: TempUnit, decimals: usize) -> String {
+    match unit {
+        TempUnit::Celsius => format!("{}°C", numbers::decimal(celsius, decimals)),
+        TempUnit::Fahrenheit => format!("{}°F", numbers::decimal(celsius * 9.0 / 5.0 + 32.0, decimals)),
+    }
+}
+
+// Busy and total jiffies of one line of /proc/stat, e.g. "cpu3 ..."
+#[derive(Clone, Copy, Debug)]
+struct CpuTimes {
+    busy: u64,
+    total: u64,
+}
+
+// The times of every "cpuN" line, in order
+fn read_cpu_times(sys: &dyn SystemInfo) -> Result<Vec<CpuTimes>, FetchError> {
+    let stat = sys.read_required("/proc/stat")?;
+
+    let times: Vec<CpuTimes> = stat.lines()
+        .filter_map(|line| {
+            let mut columns = line.split_whitespace();
+            // The bare "cpu" line is all of them together
+            let name = columns.next()?.strip_prefix("cpu")?;
+            if name.is_empty() {
+                return None;
+            }
+
+            // user nice system idle iowait irq softirq steal, guest time is
+            // already counted in user
+            let values: Vec<u64> = columns.take(8).filter_map(|value| value.parse().ok()).collect();
+            let idle = values.get(3)? + values.get(4).unwrap_or(&0);
+            let total: u64 = values.iter().sum();
+            Some(CpuTimes { busy: total - idle, total })
+        })
+        .collect();
+
+    if times.is_empty() {
+        return Err(FetchError::Parse("/proc/stat".to_string()));
+    }
+    Ok(times)
+}
+
+// How many CPUs the kernel counts in /proc/stat
+pub fn cpu_count(sys: &dyn SystemInfo) -> Result<usize, FetchError> {
+    Ok(read_cpu_times(sys)?.len())
+}
+
+// How busy each CPU was between two reads of /proc/stat `interval` apart,
+// from 0.0 to 1.0. The counters only mean something as a difference.
+fn sample_usage(sys: &dyn SystemInfo, interval: Duration) -> Result<Vec<f64>, FetchError> {
+    let before = read_cpu_times(sys)?;
+    thread::sleep(interval);
+    let after = read_cpu_times(sys)?;
+
+    Ok(before.iter().zip(&after)
+        .map(|(before, after)| {
+            let total = after.total.saturating_sub(before.total);
+            let busy = after.busy.saturating_sub(before.busy);
+            if total == 0 { 0.0 } else { busy as f64 / total as f64 }
+        })
+        .collect())
+}
+
+// Long enough for the counters to move, short enough not to hold up the rest
+const SAMPLE_INTERVAL: Duration = Duration::from_millis(200);
+
+// One bar per core, e.g. "[▁▃█▂]"
+pub fn get_core_usage(sys: &dyn SystemInfo) -> Result<String, FetchError> {
+    const BARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+    let bars: String = sample_usage(sys, SAMPLE_INTERVAL)?.iter()
+        .map(|usage| BARS[(usage * (BARS.len() - 1) as f64).round() as usize])
+        .collect();
+
+    Ok(format!("[{}]", bars))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::system::mock::MockSystem;
+
+    #[test]
+    fn cpu_name_that_is_all_noise() {
+        for name in ["Processor", "CPU @ 2.0GHz"] {
+            let sys = MockSystem::default().file("/proc/cpuinfo", &format!("model name\t: {}\n", name));
+
+            assert_eq!(get_cpu(&sys, false).unwrap(), name);
+        }
+
+        let sys = MockSystem::default().file("/proc/cpuinfo", "model name\t: Intel(R) Core(TM) i7-1165G7 CPU @ 2.80GHz\n");
+        assert_eq!(get_cpu(&sys, false).unwrap(), "Intel Core i7-1165G7");
+        assert!(get_cpu(&MockSystem::default().file("/proc/cpuinfo", "model name\t: \n"), false).is_err());
+    }
+
+    #[test]
+    fn temperature_in_fahrenheit() {
+        assert_eq!(format_temperature(0.0, TempUnit::Fahrenheit, 0), "32°F");
+        assert_eq!(format_temperature(100.0, TempUnit::Fahrenheit, 0), "212°F");
+        assert_eq!(format_temperature(100.0, TempUnit::Celsius, 0), "100°C");
+        assert_eq!(format_temperature(36.56, TempUnit::Celsius, 1), "36.6°C");
+    }
+}
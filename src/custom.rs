@@ -0,0 +1,64 @@
+use std::process::Command;
+use crate::colors;
+use crate::config::{Config, CustomField};
+use crate::error::FetchError;
+use crate::fields;
+use crate::render;
+use crate::system::{self, SystemInfo};
+use crate::theme;
+
+// The output of a custom field's command, one entry per line, with paths
+// under the home directory shortened to "~/...". A command that fails, times
+// out or prints nothing leaves the field out, as does one that needs the
+// network when risifetch is offline.
+pub fn fetch(field: &CustomField, sys: &dyn SystemInfo, config: &Config) -> Result<Vec<String>, FetchError> {
+    if config.offline && field.network {
+        return Err(FetchError::NotAvailable(format!("{} while offline", field.key)));
+    }
+
+    let mut command = Command::new("sh");
+    command.arg("-c").arg(&field.command);
+
+    // Escape codes for the theme's colors so the output can match, empty
+    // when colors are off
+    let mode = colors::mode();
+    let theme = theme::active();
+    command
+        .env("RISIFETCH_ACCENT", theme.accent.escape(mode))
+        .env("RISIFETCH_LABEL", theme.label.escape(mode))
+        .env("RISIFETCH_VALUE", theme.value.escape(mode))
+        .env("RISIFETCH_SEPARATOR", theme.separator.escape(mode))
+        .env("RISIFETCH_RESET", colors::reset(mode));
+
+    let output = system::run_with_timeout(command, config.timeout_or_default(field.timeout_ms))
+        .map_err(|err| FetchError::Command(field.key.clone(), err))?;
+
+    // The theme's colors are welcome, cursor movement and the like aren't
+    let output = render::strip_non_color_escapes(&output);
+    let lines: Vec<String> = output.trim().lines()
+        .map(|line| fields::abbreviate_path(sys, line.trim_end()))
+        .collect();
+    if lines.is_empty() {
+        return Err(FetchError::NotAvailable(field.key.clone()));
+    }
+    Ok(lines)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::system::mock::MockSystem;
+
+    #[test]
+    fn output_paths_under_the_home_directory() {
+        let sys = MockSystem::default().env("HOME", "/home/risi");
+        let field = CustomField {
+            key: "bin".to_string(),
+            command: "echo /home/risi/.local/bin; echo /home/risi2".to_string(),
+            timeout_ms: None,
+            network: false,
+        };
+
+        assert_eq!(fetch(&field, &sys, &Config::default()).unwrap(), ["~/.local/bin", "/home/risi2"]);
+    }
+}
@@ -0,0 +1,69 @@
+use crate::error::FetchError;
+use crate::system::SystemInfo;
+
+// The value of `key` in an ini-style file, e.g. "Adwaita" out of
+// `gtk-cursor-theme-name="Adwaita"`
+fn ini_value(contents: &str, key: &str) -> Option<String> {
+    contents.lines()
+        .filter_map(|line| line.split_once('='))
+        .find(|(name, _)| name.trim() == key)
+        .map(|(_, value)| value.trim().trim_matches(|c| c == '"' || c == '\'').to_string())
+        .filter(|value| !value.is_empty())
+}
+
+// $XDG_CONFIG_HOME, or ~/.config
+fn config_home(sys: &dyn SystemInfo) -> Option<String> {
+    let config_home = sys.env_var("XDG_CONFIG_HOME")
+        .map(|path| path.to_string_lossy().into_owned())
+        .filter(|path| path.starts_with('/'));
+
+    config_home.or_else(|| Some(format!("{}/.config", home(sys)?)))
+}
+
+fn home(sys: &dyn SystemInfo) -> Option<String> {
+    sys.env_var("HOME").map(|home| home.to_string_lossy().into_owned())
+}
+
+// The cursor theme and its size when set, e.g. "Bibata-Modern-Ice (24px)".
+// GTK's settings come first, then the default X cursor theme.
+pub fn get_cursor_theme(sys: &dyn SystemInfo) -> Result<String, FetchError> {
+    let gtk_settings: Vec<String> = config_home(sys).into_iter()
+        .flat_map(|dir| ["gtk-4.0", "gtk-3.0"].map(|gtk| format!("{}/{}/settings.ini", dir, gtk)))
+        .filter_map(|path| sys.read_file(&path).ok())
+        .collect();
+
+    let from_gtk = gtk_settings.iter().find_map(|settings| {
+        let name = ini_value(settings, "gtk-cursor-theme-name")?;
+        Some((name, ini_value(settings, "gtk-cursor-theme-size")))
+    });
+    let from_icons = || {
+        let index = sys.read_file(&format!("{}/.icons/default/index.theme", home(sys)?)).ok()?;
+        Some((ini_value(&index, "Inherits")?, None))
+    };
+
+    let (name, size) = from_gtk.or_else(from_icons)
+        .ok_or_else(|| FetchError::NotAvailable("cursor theme".to_string()))?;
+    let size = size.or_else(|| sys.env_var("XCURSOR_SIZE").map(|size| size.to_string_lossy().into_owned()));
+
+    Ok(match size {
+        Some(size) => format!("{} ({}px)", name, size),
+        None => name,
+    })
+}
+
+// How many windows the X11 window manager manages, from _NET_CLIENT_LIST on
+// the root window. Wayland sessions have no such list.
+pub fn get_window_count(sys: &dyn SystemInfo) -> Result<String, FetchError> {
+    if sys.env_var("DISPLAY").is_none() {
+        return Err(FetchError::NotAvailable("X11 display".to_string()));
+    }
+
+    // e.g. "_NET_CLIENT_LIST(WINDOW): window id # 0x1200003, 0x1400003",
+    // or "_NET_CLIENT_LIST:  not found." when the WM doesn't keep one
+    let clients = sys.command_output("xprop", &["-root", "_NET_CLIENT_LIST"])
+        .map_err(|_| FetchError::NotAvailable("xprop".to_string()))?;
+    let (_, windows) = clients.split_once('#')
+        .ok_or_else(|| FetchError::NotAvailable("_NET_CLIENT_LIST".to_string()))?;
+
+    Ok(windows.split(',').filter(|window| !window.trim().is_empty()).count().to_string())
+}
@@ -0,0 +1,144 @@
+use crate::error::FetchError;
+use crate::system::SystemInfo;
+
+const DMI_ID: &str = "/sys/devices/virtual/dmi/id";
+
+// Filler that board vendors leave in the DMI tables instead of a real value
+const PLACEHOLDERS: [&str; 14] = [
+    "default string",
+    "system product name",
+    "system version",
+    "system manufacturer",
+    "to be filled by o.e.m.",
+    "to be filled by oem",
+    "o.e.m.",
+    "oem",
+    "not applicable",
+    "not specified",
+    "not available",
+    "none",
+    "type1productconfigid",
+    "0123456789",
+];
+
+// The value with whitespace trimmed, or None when it's empty or a placeholder
+pub fn sanitize(value: &str) -> Option<String> {
+    let value = value.trim();
+    let lowercase = value.to_lowercase();
+
+    if value.is_empty() || PLACEHOLDERS.contains(&lowercase.as_str()) {
+        None
+    } else {
+        Some(value.to_string())
+    }
+}
+
+// A sanitized entry of /sys/devices/virtual/dmi/id, e.g. "product_name"
+pub fn read(sys: &dyn SystemInfo, name: &str) -> Option<String> {
+    sanitize(&sys.read_file(&format!("{}/{}", DMI_ID, name)).ok()?)
+}
+
+// The machine model, e.g. "20QDCTO1WW ThinkPad X1 Carbon 7th"
+pub fn get_host_model(sys: &dyn SystemInfo) -> Result<String, FetchError> {
+    let parts: Vec<String> = ["product_name", "product_version"].iter()
+        .filter_map(|name| read(sys, name))
+        .collect();
+
+    if parts.is_empty() {
+        return Err(FetchError::NotAvailable("host model".to_string()));
+    }
+    Ok(parts.join(" "))
+}
+
+// The mainboard, e.g. "ASUS PRIME B450M-A"
+pub fn get_motherboard(sys: &dyn SystemInfo) -> Result<String, FetchError> {
+    let parts: Vec<String> = ["board_vendor", "board_name"].iter()
+        .filter_map(|name| read(sys, name))
+        .collect();
+
+    if parts.is_empty() {
+        return Err(FetchError::NotAvailable("motherboard".to_string()));
+    }
+    Ok(parts.join(" "))
+}
+
+// The kind of case the machine comes in, grouped from the SMBIOS codes
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Chassis {
+    Desktop,
+    Laptop,
+    Convertible,
+    Detachable,
+    Tablet,
+    Handheld,
+    Server,
+    Embedded,
+}
+
+impl Chassis {
+    pub fn name(self) -> &'static str {
+        match self {
+            Chassis::Desktop => "Desktop",
+            Chassis::Laptop => "Laptop",
+            Chassis::Convertible => "Convertible",
+            Chassis::Detachable => "Detachable",
+            Chassis::Tablet => "Tablet",
+            Chassis::Handheld => "Handheld",
+            Chassis::Server => "Server",
+            Chassis::Embedded => "Embedded",
+        }
+    }
+
+    // Whether it can have a battery at all
+    pub fn is_portable(self) -> bool {
+        !matches!(self, Chassis::Desktop | Chassis::Server)
+    }
+}
+
+// From chassis_type, None for "other", "unknown" and the odd rack parts
+pub fn chassis(sys: &dyn SystemInfo) -> Option<Chassis> {
+    let code: u8 = read(sys, "chassis_type")?.parse().ok()?;
+
+    match code {
+        3..=7 | 13 | 15 | 16 | 24 | 35 | 36 => Some(Chassis::Desktop),
+        8 | 9 | 10 | 14 => Some(Chassis::Laptop),
+        11 => Some(Chassis::Handheld),
+        17 | 23 | 25 | 28 | 29 => Some(Chassis::Server),
+        30 => Some(Chassis::Tablet),
+        31 => Some(Chassis::Convertible),
+        32 => Some(Chassis::Detachable),
+        33 | 34 => Some(Chassis::Embedded),
+        _ => None,
+    }
+}
+
+// e.g. "Laptop"
+pub fn get_chassis(sys: &dyn SystemInfo) -> Result<String, FetchError> {
+    chassis(sys)
+        .map(|chassis| chassis.name().to_string())
+        .ok_or_else(|| FetchError::NotAvailable("chassis type".to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::system::mock::MockSystem;
+
+    #[test]
+    fn placeholders_are_empty() {
+        assert_eq!(sanitize("To be filled by O.E.M."), None);
+        assert_eq!(sanitize("Default string\n"), None);
+        assert_eq!(sanitize("System Product Name"), None);
+        assert_eq!(sanitize("   "), None);
+        assert_eq!(sanitize(" ThinkPad X1 Carbon 7th\n"), Some("ThinkPad X1 Carbon 7th".to_string()));
+    }
+
+    #[test]
+    fn host_model_skips_placeholders() {
+        let sys = MockSystem::default()
+            .file("/sys/devices/virtual/dmi/id/product_name", "System Product Name\n")
+            .file("/sys/devices/virtual/dmi/id/product_version", "System Version\n");
+
+        assert!(matches!(get_host_model(&sys), Err(FetchError::NotAvailable(..))));
+    }
+}
@@ -0,0 +1,44 @@
+use std::path::PathBuf;
+use thiserror::Error;
+
+// Everything a field getter can fail with. The render loop skips
+// unavailable and disabled fields quietly and reports the rest on stderr.
+#[derive(Debug, Error)]
+pub enum FetchError {
+    #[error("failed reading {}: {1}", .0.display())]
+    Io(PathBuf, std::io::Error),
+
+    #[error("failed parsing {0}")]
+    Parse(String),
+
+    #[error("{0} is not available")]
+    NotAvailable(String),
+
+    // An opt-in field left off, with the setting that turns it on
+    #[error("disabled (enable with {0})")]
+    Disabled(&'static str),
+
+    #[error("no network interface named {0}")]
+    NoSuchInterface(String),
+
+    #[error("{0} failed: {1}")]
+    Sys(&'static str, std::io::Error),
+
+    #[error("command for {0} failed: {1}")]
+    Command(String, std::io::Error),
+}
+
+#[derive(Debug, Error)]
+pub enum ConfigError {
+    #[error("failed reading {}: {1}", .0.display())]
+    Io(PathBuf, std::io::Error),
+
+    #[error("invalid config {}: {1}", .0.display())]
+    Parse(PathBuf, toml::de::Error),
+
+    #[error("no profile named '{0}' in the config")]
+    UnknownProfile(String),
+
+    #[error("no field named '{0}'")]
+    UnknownField(String),
+}
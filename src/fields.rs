@@ -1,206 +1,847 @@
-use std::io::Read;
-use std::env;
-use std::fs;
+use std::collections::HashMap;
+use std::time::Duration;
 use regex::{Regex, Captures};
-use crate::colors;
-
-fn format_data(key: &str, value: &str) -> String {
-    format!("{color1}▪{bold} {key:7}{reset} {color2}{value}",
-            key = key,
-            value = value,
-            color1 = colors::yellow,
-            color2 = colors::cyan,
-            bold = colors::bold,
-            reset = colors::reset,
-            )
-}
+use serde::Serialize;
+use systemstat::Platform;
+use crate::colors::{self, Color};
+use crate::cpu;
+use crate::config::{MemoryUsed, ShellSource, UptimeFormat, UsageConfig};
+use crate::numbers;
+use crate::theme;
+use crate::error::FetchError;
+use crate::system::SystemInfo;
 
 // Search with Regex in a string and return all of the matches
-fn match_regex(search_str: &str, regex: String) -> Option<Captures> {
+fn match_regex(search_str: &str, regex: String) -> Option<Captures<'_>> {
     let re = Regex::new(&regex).unwrap();
 
     re.captures(search_str)
 }
 
-pub fn get_user_host_name() -> Result<(String, String), String> {
+// The username and hostname for the header
+pub fn get_user_host_name(sys: &dyn SystemInfo) -> Result<(String, String), FetchError> {
     // Username
-    let username_env = env::var_os("USER");
-    let username: String;
+    let username = sys.env_var("USER")
+        .map(|username| username.to_string_lossy().into_owned())
+        .unwrap_or_default();
+
+    // Hostname, a non-UTF-8 one is still better shown mangled than not at all
+    let mut buf = [0u8; 128];
+    let hostname_cstr = nix::unistd::gethostname(&mut buf)
+        .map_err(|errno| FetchError::Sys("gethostname", errno.into()))?;
+    let hostname = hostname_cstr.to_string_lossy();
+
+    Ok((username, hostname.into_owned()))
+}
 
-    if let Some(..) = username_env {
-        username = username_env.unwrap().into_string().unwrap();
+// Read a release file such as /etc/os-release and pull out the value of `key`
+fn read_release_value(sys: &dyn SystemInfo, path: &str, key: &str) -> Option<String> {
+    let buffer = sys.read_file(path).ok()?;
+
+    let re_release = match_regex(&buffer,
+                                 format!(r#"(?xm)
+                                 ^{key}=     # Anchored, so NAME= doesn't match PRETTY_NAME=
+                                 ["']?       # Quotes if the value is multiple words
+                                 (?P<value>[^\n"']+)
+                                 ["']?       # Ditto
+                                 $
+                                 "#, key = regex::escape(key)))?;
+
+    Some(re_release.name("value")?.as_str().to_string())
+}
+
+// With `short_name`, os-release's NAME (e.g. "Fedora Linux") wins over the
+// fuller PRETTY_NAME (e.g. "Fedora Linux 39 (Workstation Edition)")
+pub fn get_distro_name(sys: &dyn SystemInfo, short_name: bool) -> Result<String, FetchError> {
+    // Prefer lsb-release, then os-release
+    let mut sources = vec![("/etc/lsb-release", "DISTRIB_DESCRIPTION")];
+    if !short_name {
+        sources.push(("/etc/os-release", "PRETTY_NAME"));
+    }
+    sources.push(("/etc/os-release", "NAME"));
+    // Only meant for scripts, e.g. "fedora", but better than nothing
+    sources.push(("/etc/os-release", "ID"));
+
+    for (path, key) in sources {
+        if let Some(distro_name) = read_release_value(sys, path, key) {
+            return Ok(distro_name);
+        }
+    }
+
+    // Minimal containers often ship neither file, and a damaged one may have
+    // none of the keys, so fall back to the kernel's idea of the OS (e.g.
+    // "Linux")
+    let uname = nix::sys::utsname::uname();
+    Ok(uname.sysname().to_string())
+}
+
+// The parts of uname that describe the kernel, kept apart for JSON
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct Kernel {
+    pub sysname: String,
+    pub release: String,
+    pub machine: String,
+}
+
+pub fn get_kernel() -> Kernel {
+    let uname = nix::sys::utsname::uname();
+
+    Kernel {
+        sysname: uname.sysname().to_string(),
+        release: uname.release().to_string(),
+        machine: uname.machine().to_string(),
+    }
+}
+
+// The kernel family, e.g. "Linux" or "Darwin", for telling platforms apart
+pub fn get_os_family() -> Result<String, FetchError> {
+    Ok(get_kernel().sysname)
+}
+
+// The running risifetch's version, e.g. "2.0.0 (1a2b3c4)" when the commit was
+// set in RISIFETCH_GIT_COMMIT at build time
+pub fn get_version() -> Result<String, FetchError> {
+    let version = env!("CARGO_PKG_VERSION");
+
+    Ok(match option_env!("RISIFETCH_GIT_COMMIT").filter(|commit| !commit.is_empty()) {
+        Some(commit) => format!("{} ({})", version, commit),
+        None => version.to_string(),
+    })
+}
+
+// Just the version of a release, e.g. "6.8.0" out of "6.8.0-rc4-custom+g1234abcd"
+fn short_release(release: &str) -> Option<&str> {
+    let re_version = match_regex(release, r"^(?P<version>\d+\.\d+(\.\d+)?)".to_string())?;
+
+    Some(re_version.name("version")?.as_str())
+}
+
+// e.g. "6.5.6-300.fc39.x86_64", or "Linux/x86_64" with the kernel name.
+// `short` drops everything after the version.
+pub fn format_kernel(kernel: &Kernel, show_kern_name: bool, short: bool) -> String {
+    if show_kern_name {
+        format!("{}/{}", kernel.sysname, kernel.machine)
+    } else if short {
+        short_release(&kernel.release).unwrap_or(&kernel.release).to_string()
     } else {
-        username = String::new();
+        kernel.release.clone()
     }
+}
 
-    // Hostname
-    let mut buf = [0u8; 128];
-    let hostname_cstr = nix::unistd::gethostname(&mut buf)
-        .map_err(|_| "Failed getting hostname".to_owned())?;
-    let hostname = hostname_cstr.to_str().map_err(|_| "Failed decoding hostname")?;
-
-    // Combine username and hostname into a formatted string
-    let main_color: &str;
-    let second_color: &str;
-    main_color = colors::yellow;
-    second_color = colors::cyan;
-
-    let user_host_name = format!("{color}{bold}{user}{reset}
-                                 {bold}{color2}@{reset}{bold}{color}{host}{reset}",
-                                 user = username,
-                                 host = hostname,
-                                 color = main_color,
-                                 color2 = second_color,
-                                 bold = colors::bold,
-                                 reset = colors::reset,
-                                 ).replace(" ", "").replace("\n", "");
-
-    // Separator
-    // format: username length + @ (1) + hostname length
-
-    let user_host_name_len = username.len() + 1 + hostname.len();
-    let mut separator = String::new();
-
-    separator += colors::cyan;
-
-    for _i in 0..(user_host_name_len) {
-        separator += "-";
-    }
-    separator += colors::reset;
-
-    Ok((user_host_name, separator))
-}
-
-pub fn get_distro_name() -> Result<String, String> {
-    // First get the lsb-release file
-    let lsb_release = fs::File::open("/etc/lsb-release");
-    let mut buffer = String::new();
-
-    // Check if lsb_release exists
-    if let Ok(..) = lsb_release {
-        // Read lsb_release into buffer
-        let mut lsb_release = lsb_release.unwrap();
-        let result = lsb_release.read_to_string(&mut buffer);
-
-        if result.is_err() { return Err("error".to_string()); }
-
-        // Match regex in buffer
-        let re_lsb = match_regex(&buffer,
-                                 r#"(?x)
-                                 DISTRIB_DESCRIPTION=
-                                 "?   # Quotes if description is multiple words
-                                 (?P<distro_name>[^\n"]+)
-                                 "?   # Ditto
-                                 \n
-                                 "#.to_string());
-
-        // Check if regex matches
-        if let Some(..) = re_lsb {
-            let re_lsb = re_lsb.unwrap();
-
-            let distro_name = re_lsb.name("distro_name")
-                .unwrap()
-                .as_str();
-            return Ok(format_data("os", distro_name));
+// The last component of a shell's path, None for "" or a bare directory
+fn shell_name(path: &str) -> Option<String> {
+    let re_shell = match_regex(path,
+                               r#"(?x)
+                               (?P<shell_name>[^/]+)$
+                               "#.to_string())?;
+
+    Some(re_shell.name("shell_name")?.as_str().to_string())
+}
+
+// The user's login shell from /etc/passwd, or from the rest of the passwd
+// database (e.g. LDAP) for users who aren't in it
+fn login_shell(sys: &dyn SystemInfo) -> Option<String> {
+    let uid = nix::unistd::getuid().to_string();
+    let from_file = sys.read_file("/etc/passwd").ok().and_then(|passwd| {
+        passwd.lines()
+            .map(|line| line.split(':').collect::<Vec<_>>())
+            .find(|entry| entry.len() == 7 && entry[2] == uid)
+            .map(|entry| entry[6].to_string())
+    });
+    let shell = match from_file {
+        Some(shell) => shell,
+        None => nix::unistd::User::from_uid(nix::unistd::getuid()).ok()??.shell.to_string_lossy().into_owned(),
+    };
+
+    shell_name(&shell)
+}
+
+// The program that started risifetch, usually the shell it was typed into,
+// which needn't be the login shell (e.g. fish run from bash)
+fn running_shell(sys: &dyn SystemInfo) -> Option<String> {
+    let comm = sys.read_file(&format!("/proc/{}/comm", std::os::unix::process::parent_id())).ok()?;
+
+    Some(comm.trim().trim_start_matches('-').to_string()).filter(|name| !name.is_empty())
+}
+
+// $SHELL, or the login shell when it's missing or doesn't name a program.
+// With ShellSource::Running it's the shell actually running, when known.
+pub fn get_shell(sys: &dyn SystemInfo, source: ShellSource) -> Result<String, FetchError> {
+    if source == ShellSource::Running {
+        if let Some(shell) = running_shell(sys) {
+            return Ok(shell);
         }
     }
 
-    // If no lsb-release then fetch os-release
-    let os_release = fs::File::open("/etc/os-release");
+    let shell = sys.env_var("SHELL")
+        .and_then(|shell| shell_name(&shell.to_string_lossy()))
+        .or_else(|| login_shell(sys));
 
-    if os_release.is_err() {
-        return Err("Error".to_string());
+    shell.ok_or_else(|| FetchError::NotAvailable("shell".to_string()))
+}
+
+// Collapse the home directory starting any path in `text` to "~", e.g.
+// "/home/risi/.local/bin" to "~/.local/bin"
+pub fn abbreviate_path(sys: &dyn SystemInfo, text: &str) -> String {
+    let Some(home) = sys.env_var("HOME").map(|home| home.to_string_lossy().into_owned()) else {
+        return text.to_string();
+    };
+    let home = home.trim_end_matches('/');
+    if home.is_empty() {
+        return text.to_string();
     }
 
-    let mut os_release = os_release.unwrap();
-    let result = os_release.read_to_string(&mut buffer);
+    // Only whole paths, so /home/risi doesn't eat into /home/risi2
+    let re_home = Regex::new(&format!(r#"(^|[\s'"=:(]){}(/|$|[\s'")])"#, regex::escape(home))).unwrap();
+    re_home.replace_all(text, "${1}~${2}").into_owned()
+}
 
-    if result.is_err() { return Err("error".to_string()); }
+// Time since boot or left on the battery, kept as data so it can be formatted
+// in different ways
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+pub struct Uptime {
+    pub secs: u64,
+    pub days: u64,
+    pub hours: u64,
+    pub minutes: u64,
+}
 
-    let re_os = match_regex(&buffer,
-                            r#"(?x)
-                            NAME=
-                            "?   # Quotes if description is multiple words
-                            (?P<distro_name>[^\n"]+)
-                            "?   # Ditto
-                            \n
-                            "#.to_string()
-    );
+// Time since boot from the first source that has it: /proc/uptime, then
+// systemstat, then the CLOCK_BOOTTIME clock, as containers and odd kernels
+// don't always offer all three
+pub fn boot_time(sys: &dyn SystemInfo, stat: &systemstat::System) -> Result<Duration, FetchError> {
+    let clock = || nix::time::clock_gettime(nix::time::ClockId::CLOCK_BOOTTIME).ok().map(Duration::from);
 
-    if let Some(..) = re_os {
-        let re_os = re_os.unwrap();
+    boot_time_from(sys, &|| stat.uptime().ok(), &clock)
+}
 
-        let distro_name = re_os.name("distro_name")
-            .unwrap()
-            .as_str();
-        return Ok(format_data("os", distro_name));
+// Ditto with the sources after /proc/uptime passed in, so tests can take
+// each of them away
+fn boot_time_from(sys: &dyn SystemInfo,
+                  systemstat: &dyn Fn() -> Option<Duration>,
+                  clock: &dyn Fn() -> Option<Duration>) -> Result<Duration, FetchError> {
+    let proc_uptime = sys.read_file("/proc/uptime").ok()
+        .and_then(|uptime| uptime.split_whitespace().next()?.parse::<f64>().ok())
+        .map(Duration::from_secs_f64);
+
+    proc_uptime
+        .or_else(systemstat)
+        .or_else(clock)
+        .ok_or_else(|| FetchError::NotAvailable("uptime".to_string()))
+}
+
+// Share of the time since boot the CPUs spent idle, e.g. "87%". The second
+// figure of /proc/uptime adds up the idle time of every CPU.
+pub fn get_idle(sys: &dyn SystemInfo) -> Result<String, FetchError> {
+    let uptime = sys.read_required("/proc/uptime")?;
+    let figures: Vec<f64> = uptime.split_whitespace()
+        .map(|figure| figure.parse::<f64>())
+        .collect::<Result<_, _>>()
+        .map_err(|_| FetchError::Parse("/proc/uptime".to_string()))?;
+
+    let (total, idle) = match figures[..] {
+        [total, idle, ..] if total > 0.0 => (total, idle),
+        _ => return Err(FetchError::NotAvailable("idle time in /proc/uptime".to_string())),
+    };
+    let cpus = cpu::cpu_count(sys)? as f64;
+
+    Ok(format!("{:.0}%", (idle / (total * cpus) * 100.0).clamp(0.0, 100.0)))
+}
+
+// Days, hours and minutes of a duration
+pub fn get_uptime(time: Duration) -> Uptime {
+    let secs = time.as_secs();
+
+    Uptime {
+        secs,
+        days: secs / (24 * 60 * 60),
+        hours: (secs % (24 * 60 * 60)) / (60 * 60),
+        minutes: (secs % (60 * 60)) / 60,
+    }
+}
+
+pub fn format_uptime(uptime: &Uptime, format: UptimeFormat) -> String {
+    if format == UptimeFormat::Pretty {
+        return format_pretty_uptime(uptime);
     }
 
-    Err("error".to_string())
+    if uptime.days > 0 {
+        format!("{days}d {hours}h {minutes}m",
+                days = uptime.days,
+                hours = uptime.hours,
+                minutes = uptime.minutes)
+    } else {
+        format!("{hours}h {minutes}m",
+                hours = uptime.hours,
+                minutes = uptime.minutes)
+    }
 }
 
-pub fn get_kernel(show_kern_name: bool) -> Result<String, String> {
-    let uname = nix::sys::utsname::uname();
-    Ok(format_data(
-        "kernel",
-        &if show_kern_name {
-            format!("{}/{}", uname.sysname(), uname.machine())
-        } else {
-            format!("{}", uname.release())
-        }))
+// e.g. "2 days, 1 hour", leaving out whatever is zero
+fn format_pretty_uptime(uptime: &Uptime) -> String {
+    let parts: Vec<String> = [(uptime.days, "day"), (uptime.hours, "hour"), (uptime.minutes, "minute")]
+        .iter()
+        .filter(|(count, _)| *count > 0)
+        .map(|(count, unit)| format!("{} {}{}", count, unit, if *count == 1 { "" } else { "s" }))
+        .collect();
+
+    if parts.is_empty() {
+        return "0 minutes".to_string();
+    }
+    parts.join(", ")
+}
+
+// The 1, 5 and 15 minute load averages, e.g. "0.52 0.58 0.59"
+pub fn get_load(sys: &dyn SystemInfo, decimals: usize) -> Result<String, FetchError> {
+    let loadavg = sys.read_required("/proc/loadavg")?;
+
+    let averages: Vec<String> = loadavg.split_whitespace()
+        .take(3)
+        .map(|average| average.parse::<f64>().map(|average| numbers::decimal(average, decimals)))
+        .collect::<Result<_, _>>()
+        .map_err(|_| FetchError::Parse("/proc/loadavg".to_string()))?;
+
+    if averages.len() < 3 {
+        return Err(FetchError::Parse("/proc/loadavg".to_string()));
+    }
+    Ok(averages.join(" "))
 }
 
-pub fn get_shell() -> Result<String, String> {
-    let shell_env = env::var_os("SHELL");
+// Sizes in binary units with one decimal, e.g. "2.4 GiB"
+pub fn humanize_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KiB", "MiB", "GiB", "TiB"];
 
-    if shell_env.is_none() {
-        return Err("Error".to_string());
+    let mut value = bytes as f64;
+    let mut unit = 0;
+
+    // Compare what will be shown, so 1023.96 KiB becomes "1.0 MiB" rather
+    // than "1024.0 KiB"
+    let shown = |value: f64, unit: usize| if unit == 0 { value } else { (value * 10.0).round() / 10.0 };
+    while shown(value, unit) >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
     }
 
-    let shell = shell_env.unwrap().into_string().unwrap();
+    if unit == 0 {
+        format!("{} {}", numbers::integer(bytes), UNITS[0])
+    } else {
+        format!("{} {}", numbers::decimal(value, 1), UNITS[unit])
+    }
+}
 
-    let re_shell = match_regex(&shell,
-                               r#"(?x)
-                               (?P<shell_name>[^/]+)$
-                               "#.to_string());
+// Green, yellow or red for how full something is, None when usage colors
+// are off
+pub fn usage_color(percent: u64, usage: &UsageConfig) -> Option<Color> {
+    if !usage.colors {
+        return None;
+    }
+
+    Some(if percent < u64::from(usage.yellow_at) {
+        Color::Green
+    } else if percent < u64::from(usage.red_at) {
+        Color::Yellow
+    } else {
+        Color::Red
+    })
+}
+
+// Draw a value in its usage color, going back to the value color after it
+fn paint_usage(text: String, percent: u64, usage: &UsageConfig) -> String {
+    match usage_color(percent, usage) {
+        Some(color) => {
+            let mode = colors::mode();
+            format!("{}{}{}", color.escape(mode), text, theme::active().value.escape(mode))
+        }
+        None => text,
+    }
+}
+
+// Parse /proc/meminfo into byte counts keyed by name, e.g. "MemTotal"
+fn read_meminfo(sys: &dyn SystemInfo) -> Result<HashMap<String, u64>, FetchError> {
+    let meminfo = sys.read_required("/proc/meminfo")?;
+    let mut values = HashMap::new();
+
+    for line in meminfo.lines() {
+        let mut parts = line.split_whitespace();
+        let (Some(key), Some(value)) = (parts.next(), parts.next()) else {
+            continue;
+        };
+        let value: u64 = value.parse()
+            .map_err(|_| FetchError::Parse("/proc/meminfo".to_string()))?;
+
+        // Everything but the page counters is given in kB
+        let bytes = if parts.next() == Some("kB") { value * 1024 } else { value };
+        values.insert(key.trim_end_matches(':').to_string(), bytes);
+    }
+
+    Ok(values)
+}
+
+// Used and total memory with the percentage, e.g. "2.9 GiB / 7.6 GiB (37%)".
+// "used" is what can't be handed to applications, or with MemoryUsed::Free
+// everything that isn't free, page cache included.
+pub fn get_memory(sys: &dyn SystemInfo, used: MemoryUsed, usage: &UsageConfig) -> Result<String, FetchError> {
+    let meminfo = read_meminfo(sys)?;
+    let value = |key: &str| meminfo.get(key).copied()
+        .ok_or_else(|| FetchError::NotAvailable(format!("{} in /proc/meminfo", key)));
+
+    let total = value("MemTotal")?;
+    let unused = match used {
+        MemoryUsed::Available => value("MemAvailable")?,
+        MemoryUsed::Free => value("MemFree")?,
+    };
+    let used = total.saturating_sub(unused);
+    let percent = (used * 100).checked_div(total).unwrap_or(0);
+
+    let memory = format!("{used} / {total} ({percent}%)",
+                         used = humanize_bytes(used),
+                         total = humanize_bytes(total),
+                         percent = percent);
+    Ok(paint_usage(memory, percent, usage))
+}
+
+// Memory with the page cache and buffers broken out, to explain why "used"
+// looks high
+pub fn get_memory_detail(sys: &dyn SystemInfo, usage: &UsageConfig) -> Result<String, FetchError> {
+    let meminfo = read_meminfo(sys)?;
+    let value = |key: &str| meminfo.get(key).copied()
+        .ok_or_else(|| FetchError::NotAvailable(format!("{} in /proc/meminfo", key)));
+
+    let total = value("MemTotal")?;
+    let used = total.saturating_sub(value("MemFree")?);
+
+    let memory = format!("used {used} (cache {cached}, buffers {buffers})",
+                         used = humanize_bytes(used),
+                         cached = humanize_bytes(value("Cached")?),
+                         buffers = humanize_bytes(value("Buffers")?));
+    Ok(paint_usage(memory, (used * 100).checked_div(total).unwrap_or(0), usage))
+}
 
-    if re_shell.is_none() {
-        return Err("Error".to_string());
+// Swap usage across all devices, with `note_zram` noting when some or all of
+// it is zram, e.g. "512.0 MiB / 7.7 GiB (zram)"
+pub fn get_swap(sys: &dyn SystemInfo, note_zram: bool, usage: &UsageConfig) -> Result<String, FetchError> {
+    let meminfo = read_meminfo(sys)?;
+    let total = meminfo.get("SwapTotal").copied().unwrap_or(0);
+    let free = meminfo.get("SwapFree").copied().unwrap_or(0);
+
+    if total == 0 {
+        return Err(FetchError::NotAvailable("swap".to_string()));
     }
 
-    let re_shell = re_shell.unwrap();
+    // Compressed RAM isn't disk, which matters when reading the figure
+    let swaps = sys.read_file("/proc/swaps").unwrap_or_default();
+    let (zram, other): (Vec<&str>, Vec<&str>) = swaps.lines()
+        .skip(1)
+        .filter_map(|line| line.split_whitespace().next())
+        .partition(|device| device.starts_with("/dev/zram"));
+
+    let source = match (zram.is_empty(), other.is_empty()) {
+        _ if !note_zram => "",
+        (false, true) => " (zram)",
+        (false, false) => " (zram + disk)",
+        _ => "",
+    };
+
+    let used = total.saturating_sub(free);
+    let swap = format!("{used} / {total}{source}",
+                       used = humanize_bytes(used),
+                       total = humanize_bytes(total),
+                       source = source);
+    Ok(paint_usage(swap, used * 100 / total, usage))
+}
+
+// Counts in SI units, with a decimal only while it says something, e.g.
+// "1.2M" or "15M"
+fn humanize_count(count: u64) -> String {
+    const UNITS: [&str; 4] = ["", "K", "M", "G"];
 
-    let shell = re_shell.name("shell_name").unwrap().as_str();
-    Ok(format_data("shell", shell))
+    let mut value = count as f64;
+    let mut unit = 0;
+    while value >= 1000.0 && unit < UNITS.len() - 1 {
+        value /= 1000.0;
+        unit += 1;
+    }
+
+    if unit == 0 {
+        numbers::integer(count)
+    } else if value < 10.0 {
+        format!("{}{}", numbers::decimal(value, 1), UNITS[unit])
+    } else {
+        format!("{}{}", numbers::decimal(value, 0), UNITS[unit])
+    }
 }
 
-pub fn format_uptime(time: std::time::Duration) -> String {
-    let uptime_seconds = time.as_secs();
+// Inode usage of a filesystem, e.g. "inodes: 1.2M / 15M (8%)". Filesystems
+// like btrfs allocate them on demand and have no total to report.
+pub fn format_inodes(fs: &systemstat::Filesystem) -> Option<String> {
+    let total = fs.files_total as u64;
+    if total == 0 {
+        return None;
+    }
+    let used = fs.files as u64;
 
-    // Calculate the uptime in hours and minutes respectively
-    let uptime_hours = uptime_seconds / (60 * 60);
-    let uptime_minutes = (uptime_seconds % (60 * 60)) / 60;
+    Some(format!("inodes: {used} / {total} ({percent}%)",
+                 used = humanize_count(used),
+                 total = humanize_count(total),
+                 percent = used * 100 / total))
+}
 
-    format_data(
-        "uptime",
-        &format!("{hours}h {minutes}m",
-                 hours = uptime_hours,
-                 minutes = uptime_minutes))
+// Usage of a filesystem, the percentage turns `warn_color` past `warn_at`
+pub fn format_disk(fs: systemstat::Filesystem,
+                   warn_at: Option<u8>,
+                   warn_color: Color,
+                   usage: &UsageConfig) -> String {
+    let total = fs.total.as_u64();
+    let used = total.saturating_sub(fs.free.as_u64());
+
+    // Like df, reserved blocks count as neither used nor available
+    let usable = used + fs.avail.as_u64();
+    let percent = (used * 100).checked_div(usable).unwrap_or(0);
+
+    let mode = colors::mode();
+    let value_color = usage_color(percent, usage).unwrap_or(theme::active().value);
+    let percent_color = match warn_at {
+        Some(warn_at) if percent > u64::from(warn_at) => warn_color,
+        _ => value_color,
+    };
+
+    format!("{value_color}{used} / {total} ({color}{percent}%{value_color}){reset_color}",
+            used = humanize_bytes(used),
+            total = humanize_bytes(total),
+            percent = percent,
+            color = percent_color.escape(mode),
+            value_color = value_color.escape(mode),
+            reset_color = theme::active().value.escape(mode))
 }
 
-pub fn format_memory(mem: systemstat::Memory) -> String {
-    format_data(
-        "memory",
-        &format!("{used} / {total}",
-                 used = systemstat::saturating_sub_bytes(mem.total, mem.free),
-                 total = mem.total))
+// The charge in percent, kept within 0 to 100 as freshly calibrated
+// batteries can report a bit over. When ACPI reports garbage the battery's
+// own capacity file is asked instead.
+fn battery_percent(sys: &dyn SystemInfo, capacity: f32) -> Result<u64, FetchError> {
+    if capacity.is_finite() {
+        return Ok((capacity * 100.0).clamp(0.0, 100.0) as u64);
+    }
+
+    let path = format!("{}/capacity", battery_dir(sys)?);
+    read_sysfs_number(sys, &path)
+        .map(|percent| percent.min(100))
+        .ok_or(FetchError::Parse(path))
 }
 
-pub fn format_battery(battery: systemstat::BatteryLife) -> String {
-    format_data(
-        "battery",
-        &format!("{percent}%, {hours}h {minutes}m remaining",
-                 percent = (battery.remaining_capacity * 100.0).trunc(),
-                 hours = battery.remaining_time.as_secs() / 3600,
-                 minutes = battery.remaining_time.as_secs() % 60))
+// e.g. "87%, 3h 12m remaining", with days like the uptime for estimates past
+// a day. The estimate is left out when it's turned off, when there's none and
+// once the battery is full.
+pub fn format_battery(sys: &dyn SystemInfo,
+                      battery: systemstat::BatteryLife,
+                      remaining_time: bool,
+                      format: UptimeFormat) -> Result<String, FetchError> {
+    let percent = battery_percent(sys, battery.remaining_capacity)?;
+    let remaining = get_uptime(battery.remaining_time);
+
+    if !remaining_time || remaining.secs == 0 || percent >= 100 {
+        return Ok(format!("{}%", percent));
+    }
+
+    Ok(format!("{}%, {} remaining", percent, format_uptime(&remaining, format)))
+}
+
+// sysfs directory of the first battery, e.g. /sys/class/power_supply/BAT0
+fn battery_dir(sys: &dyn SystemInfo) -> Result<String, FetchError> {
+    const POWER_SUPPLY: &str = "/sys/class/power_supply";
+
+    let mut batteries: Vec<String> = sys.read_dir(POWER_SUPPLY)
+        .map_err(|_| FetchError::NotAvailable("battery".to_string()))?
+        .into_iter()
+        .filter(|name| name.starts_with("BAT"))
+        .collect();
+    batteries.sort();
+
+    batteries.first()
+        .map(|name| format!("{}/{}", POWER_SUPPLY, name))
+        .ok_or_else(|| FetchError::NotAvailable("battery".to_string()))
+}
+
+// Read a sysfs file holding a single number
+fn read_sysfs_number(sys: &dyn SystemInfo, path: &str) -> Option<u64> {
+    sys.read_file(path).ok()?.trim().parse().ok()
+}
+
+// How much charge the battery can still hold compared to when it was new
+pub fn get_battery_health(sys: &dyn SystemInfo) -> Result<String, FetchError> {
+    let dir = battery_dir(sys)?;
+
+    // Depending on the driver capacity is reported in µWh or µAh
+    for unit in ["energy", "charge"] {
+        let full = read_sysfs_number(sys, &format!("{}/{}_full", dir, unit));
+        let design = read_sysfs_number(sys, &format!("{}/{}_full_design", dir, unit));
+
+        if let (Some(full), Some(design)) = (full, design) {
+            if design == 0 {
+                return Err(FetchError::Parse(format!("{}/{}_full_design", dir, unit)));
+            }
+            return Ok(format!("{}%", full * 100 / design));
+        }
+    }
+
+    Err(FetchError::NotAvailable("battery design capacity".to_string()))
+}
+
+// Charge cycles the battery has been through, not every battery counts them
+pub fn get_battery_cycles(sys: &dyn SystemInfo) -> Result<String, FetchError> {
+    let path = format!("{}/cycle_count", battery_dir(sys)?);
+
+    match read_sysfs_number(sys, &path) {
+        Some(cycles) => Ok(cycles.to_string()),
+        None => Err(FetchError::NotAvailable(path)),
+    }
+}
+
+// Whether the firmware enforces Secure Boot, from its EFI variable. BIOS
+// machines have no EFI variables at all.
+pub fn get_secure_boot(sys: &dyn SystemInfo) -> Result<String, FetchError> {
+    const SECURE_BOOT: &str =
+        "/sys/firmware/efi/efivars/SecureBoot-8be4df61-93ca-11d2-aa0d-00e098032b8c";
+
+    let variable = sys.read_file(SECURE_BOOT)
+        .map_err(|_| FetchError::NotAvailable("Secure Boot variable".to_string()))?;
+
+    // Four bytes of attributes, then the value itself
+    match variable.as_bytes().get(4) {
+        Some(1) => Ok("enabled".to_string()),
+        Some(0) => Ok("disabled".to_string()),
+        _ => Err(FetchError::Parse(SECURE_BOOT.to_string())),
+    }
+}
+
+// How many systemd units have failed, in red when any have. systemd creates
+// /run/systemd/system when it boots the machine.
+pub fn get_failed_units(sys: &dyn SystemInfo) -> Result<String, FetchError> {
+    if sys.read_dir("/run/systemd/system").is_err() {
+        return Err(FetchError::NotAvailable("systemd".to_string()));
+    }
+
+    let units = sys.command_output("systemctl", &["--failed", "--no-legend", "--plain"])
+        .map_err(|err| FetchError::Command("failed units".to_string(), err))?;
+    let count = units.lines().filter(|line| !line.trim().is_empty()).count();
+
+    if count == 0 {
+        return Ok("0".to_string());
+    }
+    let mode = colors::mode();
+    Ok(format!("{}{}{}", Color::Red.escape(mode), count, theme::active().value.escape(mode)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::system::mock::MockSystem;
+
+    const FEDORA_OS_RELEASE: &str = r#"NAME="Fedora Linux"
+VERSION="39 (Workstation Edition)"
+ID=fedora
+VERSION_ID=39
+PRETTY_NAME="Fedora Linux 39 (Workstation Edition)"
+"#;
+
+    #[test]
+    fn distro_name_prefers_pretty_name() {
+        let sys = MockSystem::default().file("/etc/os-release", FEDORA_OS_RELEASE);
+
+        assert_eq!(get_distro_name(&sys, false).unwrap(), "Fedora Linux 39 (Workstation Edition)");
+    }
+
+    #[test]
+    fn distro_name_short_form() {
+        let sys = MockSystem::default().file("/etc/os-release", FEDORA_OS_RELEASE);
+
+        assert_eq!(get_distro_name(&sys, true).unwrap(), "Fedora Linux");
+    }
+
+    #[test]
+    fn distro_name_without_pretty_name() {
+        let sys = MockSystem::default().file("/etc/os-release", "NAME=Alpine\nID=alpine\n");
+
+        assert_eq!(get_distro_name(&sys, false).unwrap(), "Alpine");
+    }
+
+    #[test]
+    fn distro_name_prefers_lsb_release() {
+        let sys = MockSystem::default()
+            .file("/etc/lsb-release", "DISTRIB_ID=risiOS\nDISTRIB_DESCRIPTION=\"risiOS 36\"\n")
+            .file("/etc/os-release", FEDORA_OS_RELEASE);
+
+        assert_eq!(get_distro_name(&sys, false).unwrap(), "risiOS 36");
+    }
+
+    #[test]
+    fn distro_name_without_release_files() {
+        let sys = MockSystem::default();
+
+        assert_eq!(get_distro_name(&sys, false).unwrap(), nix::sys::utsname::uname().sysname());
+    }
+
+    #[test]
+    fn uptime_from_proc() {
+        let sys = MockSystem::default().file("/proc/uptime", "12345.67 45678.90\n");
+
+        assert_eq!(boot_time(&sys, &systemstat::System::new()).unwrap().as_secs(), 12345);
+    }
+
+    #[test]
+    fn uptime_without_proc() {
+        let secs = |secs: u64| move || Some(Duration::from_secs(secs));
+        let missing = || None;
+        let with_proc = MockSystem::default().file("/proc/uptime", "300.5 600.0\n");
+        let sys = MockSystem::default();
+
+        assert_eq!(boot_time_from(&with_proc, &secs(100), &secs(200)).unwrap().as_secs(), 300);
+        assert_eq!(boot_time_from(&sys, &secs(100), &secs(200)).unwrap().as_secs(), 100);
+        assert_eq!(boot_time_from(&sys, &missing, &secs(200)).unwrap().as_secs(), 200);
+        assert!(boot_time_from(&sys, &missing, &missing).is_err());
+    }
+
+    #[test]
+    fn distro_name_falls_back_to_id() {
+        let sys = MockSystem::default().file("/etc/os-release", "ID=void\nBUILD_ID=rolling\n");
+
+        assert_eq!(get_distro_name(&sys, false).unwrap(), "void");
+    }
+
+    // One Latin-1 byte mangles a character, not the whole name
+    #[test]
+    fn distro_name_with_invalid_utf8() {
+        let sys = MockSystem::default().file_bytes("/etc/os-release", b"PRETTY_NAME=\"Caf\xe9 OS 2\"\nID=cafe\n");
+
+        assert_eq!(get_distro_name(&sys, false).unwrap(), "Caf\u{fffd} OS 2");
+    }
+
+    #[test]
+    fn distro_name_from_malformed_os_release() {
+        let uname = nix::sys::utsname::uname();
+
+        for os_release in ["\u{0}\u{1}garbage\u{fffd}", "PRETTYNAME=Fedora\n NAME =x\n", "# ID=fedora\n"] {
+            let sys = MockSystem::default().file("/etc/os-release", os_release);
+
+            assert_eq!(get_distro_name(&sys, false).unwrap(), uname.sysname(), "{:?}", os_release);
+        }
+    }
+
+    fn battery(remaining_capacity: f32) -> systemstat::BatteryLife {
+        systemstat::BatteryLife { remaining_capacity, remaining_time: Duration::from_secs(3 * 3600 + 12 * 60) }
+    }
+
+    #[test]
+    fn battery_percentage_clamped() {
+        let sys = MockSystem::default();
+
+        assert_eq!(format_battery(&sys, battery(1.07), true, UptimeFormat::Compact).unwrap(), "100%");
+        assert_eq!(format_battery(&sys, battery(-0.2), false, UptimeFormat::Compact).unwrap(), "0%");
+        assert_eq!(format_battery(&sys, battery(0.87), true, UptimeFormat::Compact).unwrap(), "87%, 3h 12m remaining");
+
+        let e_reader = || systemstat::BatteryLife { remaining_capacity: 0.9, remaining_time: Duration::from_secs(50 * 3600) };
+        assert_eq!(format_battery(&sys, e_reader(), true, UptimeFormat::Compact).unwrap(), "90%, 2d 2h 0m remaining");
+        assert_eq!(format_battery(&sys, e_reader(), true, UptimeFormat::Pretty).unwrap(), "90%, 2 days, 2 hours remaining");
+    }
+
+    #[test]
+    fn battery_percentage_from_sysfs() {
+        let sys = MockSystem::default().file("/sys/class/power_supply/BAT0/capacity", "64\n");
+
+        assert_eq!(format_battery(&sys, battery(f32::NAN), false, UptimeFormat::Compact).unwrap(), "64%");
+        assert!(format_battery(&MockSystem::default(), battery(f32::INFINITY), false, UptimeFormat::Compact).is_err());
+    }
+
+    const MEMINFO: &str = "MemTotal:        8000000 kB
+MemFree:         1000000 kB
+MemAvailable:    5000000 kB
+Buffers:          250000 kB
+Cached:          3000000 kB
+SwapTotal:             0 kB
+";
+
+    #[test]
+    fn memory_used_is_total_minus_available() {
+        let sys = MockSystem::default().file("/proc/meminfo", MEMINFO);
+
+        assert_eq!(get_memory(&sys, MemoryUsed::Available, &UsageConfig::default()).unwrap(), "2.9 GiB / 7.6 GiB (37%)");
+    }
+
+    #[test]
+    fn memory_used_is_total_minus_free() {
+        let sys = MockSystem::default().file("/proc/meminfo", MEMINFO);
+
+        assert_eq!(get_memory(&sys, MemoryUsed::Free, &UsageConfig::default()).unwrap(), "6.7 GiB / 7.6 GiB (87%)");
+    }
+
+    #[test]
+    fn memory_detail_from_meminfo() {
+        let sys = MockSystem::default().file("/proc/meminfo", MEMINFO);
+
+        assert_eq!(get_memory_detail(&sys, &UsageConfig::default()).unwrap(),
+                   "used 6.7 GiB (cache 2.9 GiB, buffers 244.1 MiB)");
+    }
+
+    #[test]
+    fn swap_notes_zram_when_asked() {
+        let sys = MockSystem::default()
+            .file("/proc/meminfo", "SwapTotal:       8388608 kB\nSwapFree:        7864320 kB\n")
+            .file("/proc/swaps", "Filename\tType\tSize\tUsed\tPriority\n/dev/zram0\tpartition\t8388604\t524288\t100\n");
+        let usage = UsageConfig::default();
+
+        assert_eq!(get_swap(&sys, true, &usage).unwrap(), "512.0 MiB / 8.0 GiB (zram)");
+        assert_eq!(get_swap(&sys, false, &usage).unwrap(), "512.0 MiB / 8.0 GiB");
+    }
+
+    #[test]
+    fn memory_detail_without_meminfo() {
+        let sys = MockSystem::default();
+
+        let err = get_memory_detail(&sys, &UsageConfig::default()).unwrap_err();
+        assert!(err.to_string().starts_with("failed reading /proc/meminfo: "), "{}", err);
+    }
+
+    #[test]
+    fn bytes_just_under_a_unit() {
+        assert_eq!(humanize_bytes(1023), "1023 B");
+        assert_eq!(humanize_bytes(1048575), "1.0 MiB");
+        assert_eq!(humanize_bytes(1073741823), "1.0 GiB");
+        assert_eq!(humanize_bytes(1024 * 1024 * 1000), "1000.0 MiB");
+    }
+
+    #[test]
+    fn username_with_invalid_utf8() {
+        use std::os::unix::ffi::OsStringExt;
+
+        let username = std::ffi::OsString::from_vec(b"ren\xe9".to_vec());
+        let sys = MockSystem::default().env("USER", username);
+
+        assert_eq!(get_user_host_name(&sys).unwrap().0, "ren\u{fffd}");
+    }
+
+    #[test]
+    fn shell_name_from_path() {
+        let sys = MockSystem::default().env("SHELL", "/usr/bin/zsh");
+
+        assert_eq!(get_shell(&sys, ShellSource::Login).unwrap(), "zsh");
+    }
+
+    #[test]
+    fn shell_name_without_directory() {
+        let sys = MockSystem::default().env("SHELL", "zsh");
+
+        assert_eq!(get_shell(&sys, ShellSource::Login).unwrap(), "zsh");
+    }
+
+    #[test]
+    fn shell_falls_back_to_login_shell() {
+        let uid = nix::unistd::getuid().as_raw();
+        let passwd = format!("other:x:{}:1000::/home/other:/bin/bash\nrisi:x:{}:1000::/home/risi:/usr/bin/fish\n",
+                             uid + 1, uid);
+
+        for shell in ["", "/bin/"] {
+            let sys = MockSystem::default().env("SHELL", shell).file("/etc/passwd", &passwd);
+
+            assert_eq!(get_shell(&sys, ShellSource::Login).unwrap(), "fish", "SHELL={:?}", shell);
+        }
+    }
 }
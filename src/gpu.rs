@@ -0,0 +1,345 @@
+use crate::config::GpuMultiple;
+use crate::error::FetchError;
+use crate::fields;
+use crate::system::SystemInfo;
+
+const PCI_DEVICES: &str = "/sys/bus/pci/devices";
+const PCI_IDS: [&str; 2] = ["/usr/share/hwdata/pci.ids", "/usr/share/misc/pci.ids"];
+
+const VENDOR_INTEL: u16 = 0x8086;
+const VENDOR_AMD: u16 = 0x1002;
+const VENDOR_NVIDIA: u16 = 0x10de;
+
+// PCI class 03 is "display controller", 0302 the headless "3D controller"
+// that Optimus laptops use for their discrete card
+const CLASS_DISPLAY: u32 = 0x03;
+const CLASS_3D: u32 = 0x0302;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GpuKind {
+    Integrated,
+    Discrete,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Gpu {
+    // PCI address, e.g. 0000:01:00.0
+    pub address: String,
+    pub vendor_id: u16,
+    pub device_id: u16,
+    pub class: u32,
+    pub name: String,
+    // None when there's no telling
+    pub kind: Option<GpuKind>,
+}
+
+fn read_hex(sys: &dyn SystemInfo, path: &str) -> Option<u32> {
+    let contents = sys.read_file(path).ok()?;
+
+    u32::from_str_radix(contents.trim().trim_start_matches("0x"), 16).ok()
+}
+
+fn vendor_short_name(vendor_id: u16) -> Option<&'static str> {
+    match vendor_id {
+        VENDOR_INTEL => Some("Intel"),
+        VENDOR_AMD => Some("AMD"),
+        VENDOR_NVIDIA => Some("NVIDIA"),
+        _ => None,
+    }
+}
+
+// Look the device up in the pci.ids database. Marketing names are usually
+// in brackets, e.g. "TU117M [GeForce GTX 1650 Mobile]".
+fn lookup_name(pci_ids: Option<&str>, vendor_id: u16, device_id: u16) -> String {
+    let vendor_hex = format!("{:04x}", vendor_id);
+    let device_hex = format!("\t{:04x}", device_id);
+    let mut vendor_name: Option<String> = None;
+    let mut device_name: Option<String> = None;
+
+    if let Some(pci_ids) = pci_ids {
+        let mut in_vendor = false;
+
+        for line in pci_ids.lines() {
+            if line.starts_with('#') || line.is_empty() {
+                continue;
+            }
+            if !line.starts_with('\t') {
+                if in_vendor {
+                    break;
+                }
+                if let Some(name) = line.strip_prefix(&vendor_hex) {
+                    in_vendor = true;
+                    vendor_name = Some(name.trim().to_string());
+                }
+            } else if in_vendor {
+                if let Some(name) = line.strip_prefix(&device_hex) {
+                    device_name = Some(name.trim().to_string());
+                    break;
+                }
+            }
+        }
+    }
+
+    let vendor = vendor_short_name(vendor_id)
+        .map(str::to_string)
+        .or(vendor_name)
+        .unwrap_or_else(|| format!("vendor {:04x}", vendor_id));
+
+    let device = match device_name {
+        Some(name) => match (name.find('['), name.rfind(']')) {
+            (Some(start), Some(end)) if start < end => name[start + 1..end].to_string(),
+            _ => name,
+        },
+        None => format!("device {:04x}", device_id),
+    };
+
+    format!("{} {}", vendor, device)
+}
+
+// The PCI vendor id matching the CPU, whose GPU is the integrated one
+fn cpu_vendor(sys: &dyn SystemInfo) -> Option<u16> {
+    let cpuinfo = sys.read_file("/proc/cpuinfo").ok()?;
+    let vendor = cpuinfo.lines()
+        .find(|line| line.starts_with("vendor_id"))?
+        .split(':')
+        .nth(1)?
+        .trim();
+
+    match vendor {
+        "GenuineIntel" => Some(VENDOR_INTEL),
+        "AuthenticAMD" => Some(VENDOR_AMD),
+        _ => None,
+    }
+}
+
+// With several GPUs, the one made by the CPU vendor is the integrated one and
+// the others are discrete. Anything less clear-cut stays unlabeled.
+fn classify(gpus: &mut [Gpu], cpu_vendor: Option<u16>) {
+    if gpus.len() < 2 {
+        return;
+    }
+
+    let same_vendor = gpus.iter()
+        .filter(|gpu| Some(gpu.vendor_id) == cpu_vendor && gpu.class != CLASS_3D)
+        .count();
+
+    for gpu in gpus.iter_mut() {
+        gpu.kind = if gpu.class == CLASS_3D || gpu.vendor_id == VENDOR_NVIDIA {
+            Some(GpuKind::Discrete)
+        } else if same_vendor != 1 {
+            None
+        } else if Some(gpu.vendor_id) == cpu_vendor {
+            Some(GpuKind::Integrated)
+        } else {
+            Some(GpuKind::Discrete)
+        };
+    }
+}
+
+// Every display controller on the PCI bus
+pub fn get_gpus(sys: &dyn SystemInfo) -> Result<Vec<Gpu>, FetchError> {
+    let devices = sys.read_dir(PCI_DEVICES)
+        .map_err(|_| FetchError::NotAvailable(PCI_DEVICES.to_string()))?;
+    let pci_ids = PCI_IDS.iter().find_map(|path| sys.read_file(path).ok());
+
+    let mut gpus: Vec<Gpu> = Vec::new();
+
+    for address in devices {
+        let dir = format!("{}/{}", PCI_DEVICES, address);
+        let class = match read_hex(sys, &format!("{}/class", dir)) {
+            Some(class) if class >> 16 == CLASS_DISPLAY => class >> 8,
+            _ => continue,
+        };
+        let (Some(vendor_id), Some(device_id)) = (
+            read_hex(sys, &format!("{}/vendor", dir)),
+            read_hex(sys, &format!("{}/device", dir)),
+        ) else {
+            continue;
+        };
+        let (vendor_id, device_id) = (vendor_id as u16, device_id as u16);
+
+        gpus.push(Gpu {
+            address,
+            vendor_id,
+            device_id,
+            class,
+            name: lookup_name(pci_ids.as_deref(), vendor_id, device_id),
+            kind: None,
+        });
+    }
+
+    if gpus.is_empty() {
+        return Err(FetchError::NotAvailable("gpu".to_string()));
+    }
+
+    gpus.sort_by(|a, b| a.address.cmp(&b.address));
+    classify(&mut gpus, cpu_vendor(sys));
+
+    Ok(gpus)
+}
+
+fn describe(gpu: &Gpu) -> String {
+    match gpu.kind {
+        Some(GpuKind::Integrated) => format!("{} (integrated)", gpu.name),
+        Some(GpuKind::Discrete) => format!("{} (discrete)", gpu.name),
+        None => gpu.name.clone(),
+    }
+}
+
+// e.g. "Intel UHD Graphics 620 (integrated), NVIDIA GeForce MX150 (discrete)".
+// Every card is its own PCI device, so identical ones are all there to list
+// or count.
+pub fn format_gpus(gpus: &[Gpu], multiple: GpuMultiple) -> String {
+    let mut descriptions: Vec<String> = gpus.iter().map(describe).collect();
+    if descriptions.len() == 1 {
+        return descriptions.remove(0);
+    }
+
+    match multiple {
+        GpuMultiple::Join => descriptions.join(", "),
+        GpuMultiple::List => descriptions.iter()
+            .enumerate()
+            .map(|(i, description)| format!("{}: {}", i + 1, description))
+            .collect::<Vec<_>>()
+            .join("\n"),
+        GpuMultiple::Collapse => {
+            let mut counted: Vec<(String, usize)> = Vec::new();
+            for description in descriptions {
+                match counted.iter_mut().find(|(seen, _)| *seen == description) {
+                    Some((_, count)) => *count += 1,
+                    None => counted.push((description, 1)),
+                }
+            }
+
+            counted.into_iter()
+                .map(|(description, count)| match count {
+                    1 => description,
+                    count => format!("{}x {}", count, description),
+                })
+                .collect::<Vec<_>>()
+                .join(", ")
+        }
+    }
+}
+
+// Used and total video memory in bytes of every amdgpu card, from its sysfs
+// files, along with what `classify` made of the card
+fn read_amd_vram(sys: &dyn SystemInfo, gpus: &[Gpu]) -> Vec<(Option<GpuKind>, u64, u64)> {
+    const DRM: &str = "/sys/class/drm";
+
+    let Ok(cards) = sys.read_dir(DRM) else {
+        return Vec::new();
+    };
+    let mut cards: Vec<String> = cards.into_iter()
+        .filter(|name| name.starts_with("card") && !name.contains('-'))
+        .collect();
+    cards.sort();
+
+    cards.iter()
+        .filter_map(|card| {
+            let read_file = |file: &str| sys.read_file(&format!("{}/{}/device/{}", DRM, card, file)).ok();
+            let read = |file: &str| read_file(file)?.trim().parse::<u64>().ok();
+
+            // The card's PCI address, to find it among the GPUs
+            let address = read_file("uevent").and_then(|uevent| {
+                uevent.lines().find_map(|line| line.strip_prefix("PCI_SLOT_NAME=")).map(str::to_string)
+            });
+            let kind = gpus.iter().find(|gpu| Some(&gpu.address) == address.as_ref()).and_then(|gpu| gpu.kind);
+
+            Some((kind, read("mem_info_vram_used")?, read("mem_info_vram_total")?))
+        })
+        .collect()
+}
+
+// Ditto from nvidia-smi, which reports MiB
+fn read_nvidia_vram(sys: &dyn SystemInfo) -> Option<(u64, u64)> {
+    let output = sys.command_output("nvidia-smi", &[
+        "--query-gpu=memory.used,memory.total",
+        "--format=csv,noheader,nounits",
+    ]).ok()?;
+
+    let (used, total) = output.lines().next()?.split_once(',')?;
+    let mib = |value: &str| value.trim().parse::<u64>().ok().map(|value| value * 1024 * 1024);
+
+    Some((mib(used)?, mib(total)?))
+}
+
+// Video memory of the discrete GPU, e.g. "2.1 GiB / 8.0 GiB"
+pub fn get_vram(sys: &dyn SystemInfo) -> Result<String, FetchError> {
+    let amd = read_amd_vram(sys, &get_gpus(sys).unwrap_or_default());
+    let amd_vram = |kind: Option<GpuKind>| amd.iter()
+        .find(|(card_kind, ..)| *card_kind == kind)
+        .map(|&(_, used, total)| (used, total));
+
+    // An APU's carved out memory only when there's no other card to show
+    let (used, total) = amd_vram(Some(GpuKind::Discrete))
+        .or_else(|| amd_vram(None))
+        .or_else(|| read_nvidia_vram(sys))
+        .or_else(|| amd_vram(Some(GpuKind::Integrated)))
+        .ok_or_else(|| FetchError::NotAvailable("vram".to_string()))?;
+
+    Ok(format!("{} / {}", fields::humanize_bytes(used), fields::humanize_bytes(total)))
+}
+
+// The first real device in `vulkaninfo --summary`, e.g.
+// "AMD Radeon Graphics (RADV RAPHAEL_MENDOCINO) (Vulkan 1.3)". Software
+// renderers like llvmpipe are only used when there's nothing else.
+pub fn get_vulkan(sys: &dyn SystemInfo) -> Result<String, FetchError> {
+    let summary = sys.command_output("vulkaninfo", &["--summary"])
+        .map_err(|_| FetchError::NotAvailable("vulkaninfo".to_string()))?;
+
+    // One (name, version, type) per GPUn: block
+    let mut devices: Vec<(String, String, String)> = Vec::new();
+    for line in summary.lines() {
+        let line = line.trim();
+        if line.starts_with("GPU") && line.ends_with(':') {
+            devices.push(Default::default());
+            continue;
+        }
+        let (Some(device), Some((key, value))) = (devices.last_mut(), line.split_once('=')) else {
+            continue;
+        };
+        let value = value.trim().to_string();
+        match key.trim() {
+            "deviceName" => device.0 = value,
+            "apiVersion" => device.1 = value,
+            "deviceType" => device.2 = value,
+            _ => {}
+        }
+    }
+
+    let (name, version, _) = devices.iter()
+        .filter(|device| !device.0.is_empty())
+        .min_by_key(|device| device.2 == "PHYSICAL_DEVICE_TYPE_CPU")
+        .ok_or_else(|| FetchError::NotAvailable("vulkan device".to_string()))?;
+
+    // Older releases print the packed number first, e.g. "4206830 (1.3.246)"
+    let version = version.rsplit('(').next().unwrap_or(version).trim_end_matches(')');
+    let major_minor = version.split('.').take(2).collect::<Vec<_>>().join(".");
+
+    Ok(format!("{} (Vulkan {})", name, major_minor))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::system::mock::MockSystem;
+
+    // An AMD laptop's APU probes as card0, its discrete card as a 3D
+    // controller on card1
+    #[test]
+    fn vram_of_the_discrete_card() {
+        let card = |sys: MockSystem, card: &str, address: &str, class: &str, used: &str, total: &str| sys
+            .file(&format!("/sys/bus/pci/devices/{}/class", address), class)
+            .file(&format!("/sys/bus/pci/devices/{}/vendor", address), "0x1002")
+            .file(&format!("/sys/bus/pci/devices/{}/device", address), "0x1681")
+            .file(&format!("/sys/class/drm/{}/device/uevent", card), &format!("DRIVER=amdgpu\nPCI_SLOT_NAME={}\n", address))
+            .file(&format!("/sys/class/drm/{}/device/mem_info_vram_used", card), used)
+            .file(&format!("/sys/class/drm/{}/device/mem_info_vram_total", card), total);
+        let sys = MockSystem::default().file("/proc/cpuinfo", "vendor_id\t: AuthenticAMD\n");
+        let sys = card(sys, "card0", "0000:05:00.0", "0x030000", "268435456", "536870912");
+        let sys = card(sys, "card1", "0000:03:00.0", "0x030200", "2147483648", "8589934592");
+
+        assert_eq!(get_vram(&sys).unwrap(), "2.0 GiB / 8.0 GiB");
+    }
+}
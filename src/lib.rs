@@ -0,0 +1,191 @@
+// risifetch's data collection, for programs that want the fields without the
+// fetch layout, e.g. status bars. The risifetch binary is built on top of it.
+//
+//     let config = risifetch::config::load(None)?;
+//     for field in risifetch::fetch_all(&config)? {
+//         if let Ok(value) = field.value {
+//             println!("{}: {}", field.name, value);
+//         }
+//     }
+
+use systemstat::Platform;
+
+pub mod cache;
+pub mod colors;
+pub mod config;
+pub mod cpu;
+pub mod custom;
+pub mod desktop;
+pub mod dmi;
+pub mod error;
+pub mod fields;
+pub mod gpu;
+pub mod logo;
+pub mod net;
+pub mod numbers;
+pub mod packages;
+pub mod registry;
+pub mod render;
+pub mod sysctl;
+pub mod system;
+pub mod terminal;
+pub mod theme;
+pub mod virt;
+
+use std::time::{Duration, Instant};
+use config::{Config, CustomField};
+use error::{ConfigError, FetchError};
+use registry::{Context, Field};
+use render::Mask;
+use system::RealSystem;
+
+// A field's name and its value, or why there isn't one
+#[derive(Debug)]
+pub struct FieldValue<'a> {
+    pub name: &'a str,
+    pub value: Result<String, FetchError>,
+}
+
+// The fields the risifetch binary shows: the built-in ones selected by the
+// config, then its tunables and custom fields. Values are plain text, and
+// numbers are written the C locale's way unless the program calls
+// numbers::use_locale() first.
+pub fn fetch_all(config: &Config) -> Result<Vec<FieldValue<'_>>, ConfigError> {
+    let selection = select_fields(config)?;
+    let stat = systemstat::System::new();
+    let sys = RealSystem { command_timeout: config.command_timeout() };
+    let ctx = Context {
+        sys: &sys,
+        stat: &stat,
+        config,
+        show_kern_name: false,
+    };
+    let mask = Mask::default();
+    let mut timings = Vec::new();
+
+    let mut values: Vec<FieldValue> = selection.fields.iter()
+        .map(|field| FieldValue { name: field.name, value: fetch_field(field, &ctx, &mask, &mut timings) })
+        .collect();
+    for name in &config.sysctl {
+        values.push(FieldValue { name: sysctl::key(name), value: fetch_sysctl(name, &ctx, &mask, &mut timings) });
+    }
+    for custom in &config.custom {
+        let value = fetch_custom(custom, &ctx, &mask, &mut timings).map(|lines| lines.join("\n"));
+        values.push(FieldValue { name: &custom.key, value });
+    }
+
+    // The colors depend on where risifetch's own output goes, which says
+    // nothing about where a library user's does
+    for field in &mut values {
+        if let Ok(value) = &mut field.value {
+            *value = render::strip_ansi(value);
+        }
+    }
+    Ok(values)
+}
+
+// The built-in fields shown beside the logo
+pub struct Selection {
+    pub fields: Vec<&'static Field>,
+    // Nothing was cached to go by, so the caller should save which of the
+    // fields turned out to be available (see cache::save_fields)
+    pub probe: bool,
+}
+
+// The config's fields list, else the fields found available the first time
+// around so a desktop doesn't keep trying for a battery, else every field
+// that isn't only shown on request
+pub fn select_fields(config: &Config) -> Result<Selection, ConfigError> {
+    let cached = match &config.fields {
+        Some(_) => None,
+        None => cache::load_fields(config.key()),
+    };
+    let probe = config.fields.is_none() && cached.is_none();
+
+    let fields = match (&config.fields, cached) {
+        (Some(names), _) => names.iter()
+            .map(|name| registry::find(name).ok_or_else(|| ConfigError::UnknownField(name.clone())))
+            .collect::<Result<_, _>>()?,
+        // A stale cache entry isn't the user's fault
+        (None, Some(names)) => names.iter().filter_map(|name| registry::find(name)).collect(),
+        (None, None) => registry::FIELDS.iter()
+            .filter(|field| !registry::ON_REQUEST.contains(&field.name))
+            .collect(),
+    };
+    Ok(Selection { fields, probe })
+}
+
+// Fetch a field's value, noting how long the getter took
+pub fn fetch_field<'a>(field: &'a Field,
+                       ctx: &Context,
+                       mask: &Mask,
+                       timings: &mut Vec<(&'a str, Duration)>) -> Result<String, FetchError> {
+    let start = Instant::now();
+    let value = field.fetch(ctx);
+    timings.push((field.name, start.elapsed()));
+
+    // A getter that came up with nothing has no value to show either
+    match value {
+        Ok(value) if value.trim().is_empty() => Err(FetchError::NotAvailable(field.name.to_string())),
+        value => value.map(|value| mask.apply_field(field.name, &value)),
+    }
+}
+
+// Ditto for the fields defined in the config file
+pub fn fetch_custom<'a>(custom: &'a CustomField,
+                        ctx: &Context,
+                        mask: &Mask,
+                        timings: &mut Vec<(&'a str, Duration)>) -> Result<Vec<String>, FetchError> {
+    let start = Instant::now();
+    let lines = custom::fetch(custom, ctx.sys, ctx.config);
+    timings.push((&custom.key, start.elapsed()));
+
+    lines.map(|lines| lines.iter().map(|line| mask.apply(line)).collect())
+}
+
+// Ditto for the tunables listed in the config file
+pub fn fetch_sysctl<'a>(name: &'a str,
+                        ctx: &Context,
+                        mask: &Mask,
+                        timings: &mut Vec<(&'a str, Duration)>) -> Result<String, FetchError> {
+    let start = Instant::now();
+    let value = sysctl::fetch(ctx.sys, name);
+    timings.push((sysctl::key(name), start.elapsed()));
+
+    value.map(|value| mask.apply(&value))
+}
+
+// A field by the name it's shown with, built-in, tunable or custom, with
+// its lines joined. None when there's no such field.
+pub fn fetch_named<'a>(name: &str,
+                       ctx: &Context<'a>,
+                       mask: &Mask,
+                       timings: &mut Vec<(&'a str, Duration)>) -> Option<Result<String, FetchError>> {
+    if let Some(field) = registry::find(name) {
+        return Some(fetch_field(field, ctx, mask, timings));
+    }
+    if let Some(tunable) = ctx.config.sysctl.iter().find(|tunable| sysctl::key(tunable) == name) {
+        return Some(fetch_sysctl(tunable, ctx, mask, timings));
+    }
+
+    let custom = ctx.config.custom.iter().find(|custom| custom.key == name)?;
+    Some(fetch_custom(custom, ctx, mask, timings).map(|lines| lines.join("\n")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fields_list_selects_in_order() {
+        let config = Config { fields: Some(vec!["kernel".to_string(), "os".to_string()]), ..Config::default() };
+        let selection = select_fields(&config).unwrap();
+        let names: Vec<&str> = selection.fields.iter().map(|field| field.name).collect();
+
+        assert_eq!(names, ["kernel", "os"]);
+        assert!(!selection.probe);
+
+        let config = Config { fields: Some(vec!["weather".to_string()]), ..Config::default() };
+        assert!(matches!(select_fields(&config), Err(ConfigError::UnknownField(name)) if name == "weather"));
+    }
+}
@@ -0,0 +1,91 @@
+use std::collections::BTreeMap;
+use std::sync::OnceLock;
+use regex::Regex;
+use crate::colors::{self, Color, ColorMode};
+use crate::render;
+
+pub struct Logo {
+    // The distro's ID in os-release, also the key for overriding its colors
+    pub id: &'static str,
+    // Canonical colors, {1} in the art is the first and primary one
+    pub colors: &'static [Color],
+    pub full: &'static [&'static str],
+    // Compact art for narrow terminals and tmux panes
+    pub small: Option<&'static [&'static str]>,
+}
+
+// The risiOS logo, {1} and {2} mark where each color starts
+pub static RISI: Logo = Logo {
+    id: "risios",
+    colors: &[Color::Yellow, Color::Cyan],
+    full: &[
+        "{1}                               .o        ",
+        "{1}                            °O@@@        ",
+        "{2}     RRRRRRRRRRRRRRRR    {1}°o@@@@@@        ",
+        "{2}     RRRRRRRRRRRRRRRRR. {1}O@@@@@@@@°       ",
+        "{2}     RRRR         {2}RRRR {1}*@@@@@@@@@#       ",
+        "{2}     RRRR       {1}.** {2}RRR {1}*@@@@@@@@@       ",
+        "{2}     RRRR    {1}.*@@@@° {2}RRR {1}@@@@@@@@@@      ",
+        "{2}     RRRR {1}°o@@@@@@@ {2}RRR {1}*@@@@@@@@@@      ",
+        "{2}     RRRR {1}#@@@@@@@ {2}RRR {1}*.@@@@@@@@@@.     ",
+        "{1}  *@ {2}RRRRRRRRRRRRRRRR {1}*@@@@@@@@@@@@@     ",
+        "{1}*o@@ {2}RRRRRRRRRRRRRRRR {1}.#@@@@@@@@@@@@@.   ",
+        "{1} #@@ {2}RRRR {1}@@@@@@ {2}RRRR {1}*@@@@@@@@@@@@@@o   ",
+        "{1}   @ {2}RRRR {1}@@@@@@@@ {2}RRRR {1}#@@@@@@@@@@@@@   ",
+        "{2}     RRRR {1}o#@@@@@@@ {2}RRRR {1}O@@@@@@@@@@@@.   ",
+        "{2}     RRRR      {1}<@@@@ {2}RRRR {1}.o@@@@@@@@@@O   ",
+        "{2}     RRRR            {2}RRRR {1}°oO@@@@@@@@@@   ",
+        "{2}     RRRR            {2}RRRR    {1}.°*O#@@@@@@   ",
+        "{1}                                  .@@@@@.   ",
+        "{1}                                      ***   ",
+    ],
+    small: Some(&[
+        "{2} RRRRRRRRR  {1}.o@    ",
+        "{2} RR     RR {1}°@@@@   ",
+        "{2} RR  {1}.o {2}RR {1}*@@@@@  ",
+        "{1}o{2}RRRRRRRRR {1}*@@@@@. ",
+        "{1}@{2}RR {1}@@@ {2}RR {1}#@@@@@  ",
+        "{2} RR  {1}<@ {2}RR {1}.o@@@@  ",
+        "{2} RR     RR   {1}°*@   ",
+    ]),
+};
+
+impl Logo {
+    // The logo's colors, unless the config has its own for it
+    pub fn palette<'a>(&'a self, overrides: &'a BTreeMap<String, Vec<Color>>) -> &'a [Color] {
+        overrides.get(self.id).map_or(self.colors, Vec::as_slice)
+    }
+}
+
+// Fill in the art's color placeholders, `colors` being the logo's own or the
+// ones from the config
+pub fn paint(art: &[&str], colors: &[Color], mode: ColorMode) -> Vec<String> {
+    art.iter()
+        .map(|line| {
+            let mut painted = line.to_string();
+            for (index, color) in colors.iter().enumerate() {
+                painted = painted.replace(&format!("{{{}}}", index + 1), &color.escape(mode));
+            }
+            format!("{}{}", replace_placeholders(&painted, colors::reset(mode)), colors::reset(mode))
+        })
+        .collect()
+}
+
+// Compiled once, every line of the logo goes through it
+static PLACEHOLDER: OnceLock<Regex> = OnceLock::new();
+
+// Fill in the color placeholders of a logo, one string per line. Placeholders
+// the palette has no color for leave that part uncolored.
+fn replace_placeholders(line: &str, with: &str) -> String {
+    PLACEHOLDER.get_or_init(|| Regex::new(r"\{\d+\}").unwrap())
+        .replace_all(line, with)
+        .into_owned()
+}
+
+// Columns taken by the widest line of the art
+pub fn width(art: &[&str]) -> usize {
+    art.iter()
+        .map(|line| render::display_width(&replace_placeholders(line, "")))
+        .max()
+        .unwrap_or(0)
+}
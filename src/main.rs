@@ -1,45 +1,49 @@
 use std::env;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::PathBuf;
 use std::process;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use serde_json::{json, Value};
 use systemstat::Platform;
-mod colors;
-mod fields;
+
+use risifetch::{cache, colors, config, fields, logo, numbers, registry, render, sysctl, terminal, theme};
+use risifetch::colors::{Color, ColorMode};
+use risifetch::config::{Overflow, SeparatorWidth, SmallFallback, ValueAlign, VerticalAlign};
+use risifetch::{fetch_custom, fetch_field, fetch_named, fetch_sysctl, Selection};
+use risifetch::error::{ConfigError, FetchError};
+use risifetch::registry::Context;
+use risifetch::render::Mask;
+use risifetch::system::RealSystem;
 
 // Simple system fetch tool written in Rust.
 fn main() {
 
-    let args: Vec<String> = env::args().collect();
+    let mut args = env::args();
+    // RISIFETCH_OFFLINE=1 in the environment does the same as --offline
+    let mut offline = env::var_os("RISIFETCH_OFFLINE").is_some_and(|value| !value.is_empty());
     let mut show_kern_name = false;
-    let ascii_tree: String;
-
-    ascii_tree = format!(
-
-        "   {yellow}                               .o        {reset}
-            {yellow}                            °O@@@        {reset}
-            {cyan}     RRRRRRRRRRRRRRRR    {yellow}°o@@@@@@        {reset}
-            {cyan}     RRRRRRRRRRRRRRRRR. {yellow}O@@@@@@@@°       {reset}
-            {cyan}     RRRR         {cyan}RRRR {yellow}*@@@@@@@@@#       {reset}
-            {cyan}     RRRR       {yellow}.** {cyan}RRR {yellow}*@@@@@@@@@       {reset}
-            {cyan}     RRRR    {yellow}.*@@@@° {cyan}RRR {yellow}@@@@@@@@@@      {reset}
-            {cyan}     RRRR {yellow}°o@@@@@@@ {cyan}RRR {yellow}*@@@@@@@@@@      {reset}
-            {cyan}     RRRR {yellow}#@@@@@@@ {cyan}RRR {yellow}*.@@@@@@@@@@.     {reset}
-            {yellow}  *@ {cyan}RRRRRRRRRRRRRRRR {yellow}*@@@@@@@@@@@@@     {reset}
-            {yellow}*o@@ {cyan}RRRRRRRRRRRRRRRR {yellow}.#@@@@@@@@@@@@@.   {reset}
-            {yellow} #@@ {cyan}RRRR {yellow}@@@@@@ {cyan}RRRR {yellow}*@@@@@@@@@@@@@@o   {reset}
-            {yellow}   @ {cyan}RRRR {yellow}@@@@@@@@ {cyan}RRRR {yellow}#@@@@@@@@@@@@@   {reset}
-            {cyan}     RRRR {yellow}o#@@@@@@@ {cyan}RRRR {yellow}O@@@@@@@@@@@@.   {reset}
-            {cyan}     RRRR      {yellow}<@@@@ {cyan}RRRR {yellow}.o@@@@@@@@@@O   {reset}
-            {cyan}     RRRR            {cyan}RRRR {yellow}°oO@@@@@@@@@@   {reset}
-            {cyan}     RRRR            {cyan}RRRR    {yellow}.°*O#@@@@@@   {reset}
-            {yellow}                                  .@@@@@.   {reset}
-            {yellow}                                      ***   {reset}
-        ",
-        cyan = colors::cyan,
-        yellow = colors::yellow,
-        reset = colors::reset,
-    );
+    let mut theme_name: Option<String> = None;
+    let mut random_colors = false;
+    let mut small = false;
+    let mut config_path: Option<PathBuf> = None;
+    let mut profile: Option<String> = None;
+    let mut output_path: Option<PathBuf> = None;
+    let mut verbose = false;
+    let mut oneline = false;
+    let mut json = false;
+    let mut env_output = false;
+    let mut list_fields = false;
+    let mut requested: Vec<String> = Vec::new();
+    let mut watch: Option<Duration> = None;
+    let mut private = false;
+    let mut show_timings = false;
+    let mut no_header = false;
 
     // Skip first arg as that is the program command
-    for arg in &args[1..] {
+    args.next();
+
+    while let Some(arg) = args.next() {
 
         // Convert to string slice for the comparisons
         let arg = &arg[..];
@@ -49,115 +53,543 @@ fn main() {
                 help_message()
             }
 
+            "--offline" => {
+                offline = true;
+            }
+
             "--kernel-name" | "-k" => {
                 show_kern_name = true;
             }
 
+            "--theme" | "-t" => {
+                theme_name = Some(option_value(arg, args.next()));
+            }
+
+            "--random-colors" | "-r" => {
+                random_colors = true;
+            }
+
+            "--small" | "-s" => {
+                small = true;
+            }
+
+            "--field" | "-f" => {
+                requested.push(option_value(arg, args.next()));
+            }
+
+            "--json" | "-j" => {
+                json = true;
+            }
+
+            "--env" => {
+                env_output = true;
+            }
+
+            "--list-fields" => {
+                list_fields = true;
+            }
+
+            "--oneline" | "-1" => {
+                oneline = true;
+            }
+
+            "--private" | "-p" => {
+                private = true;
+            }
+
+            "--timings" => {
+                show_timings = true;
+            }
+
+            "--no-header" => {
+                no_header = true;
+            }
+
+            "--watch" | "-w" => {
+                let value = option_value(arg, args.next());
+                match value.parse::<f64>() {
+                    Ok(secs) if secs > 0.0 && secs.is_finite() => watch = Some(Duration::from_secs_f64(secs)),
+                    _ => invalid_value(arg, &value),
+                }
+            }
+
+            "--verbose" | "-v" => {
+                verbose = true;
+            }
+
+            "--output" | "-o" => {
+                output_path = Some(PathBuf::from(option_value(arg, args.next())));
+            }
+
+            "--config" | "-c" => {
+                config_path = Some(PathBuf::from(option_value(arg, args.next())));
+            }
+
+            "--profile" | "-P" => {
+                profile = Some(option_value(arg, args.next()));
+            }
+
             _ => {
                 invalid_option(arg.to_string());
             }
         }
     }
 
-    let ascii_tree = split_by_newline(ascii_tree);
+    // Escape codes have no business in JSON or values read by scripts
+    if json || env_output || list_fields || !requested.is_empty() {
+        colors::set_mode(ColorMode::Plain);
+    } else if output_path.is_some() {
+        colors::set_mode(colors::detect_mode(true));
+    }
+    let mode = colors::mode();
 
-    let stat = systemstat::System::new();
+    if verbose {
+        eprintln!("risifetch: color support: {}", mode.name());
+    }
 
-    let mut data_list: Vec<String> = Vec::new();
-    data_list.push(String::from(""));
-    data_list.push(String::from(""));
+    let config = config::load(config_path)
+        .and_then(|mut config| {
+            if let Some(name) = &profile {
+                config.apply_profile(name)?;
+            }
+            config.offline |= offline;
+            Ok(config)
+        })
+        .unwrap_or_else(|err| {
+            eprintln!("risifetch: {}", err);
+            process::exit(1)
+        });
+
+    // Like colors, localized numbers are for people rather than scripts
+    if config.locale_numbers && !json && !env_output && requested.is_empty() {
+        numbers::use_locale();
+    }
 
-    if let Ok(value) = fields::get_user_host_name() {
-            data_list.push(value.0);
-            data_list.push(value.1);
+    // The command line wins over the config file. There's nothing to
+    // randomize when colors are off.
+    let mut theme = if random_colors && mode != ColorMode::Plain {
+        theme::random(time_seed())
+    } else if let Some(name) = theme_name.or(config.theme.clone()) {
+        match theme::find(&name) {
+            Some(theme) => *theme,
+            None => unknown_theme(&name),
+        }
+    } else {
+        theme::DEFAULT
     };
+    let palette = logo::RISI.palette(&config.logo.colors);
+    if let (true, Some(primary)) = (config.logo.accent_from_logo, palette.first()) {
+        theme.accent = *primary;
+    }
+    // Colors set one by one beat the theme's
+    theme::set_active(theme.with_overrides(&config.colors));
 
-
-    if let Ok(value) = fields::get_distro_name() {
-        data_list.push(value);
+    let stat = systemstat::System::new();
+    let sys = RealSystem { command_timeout: config.command_timeout() };
+    let ctx = Context {
+        sys: &sys,
+        stat: &stat,
+        config: &config,
+        show_kern_name,
     };
 
-    // Kernel name
+    let names = fields::get_user_host_name(&sys).map_err(report_error).ok();
 
-    if let Ok(value) = fields::get_kernel(show_kern_name) {
-        data_list.push(value);
+    let mask = match (&names, private) {
+        (Some((username, hostname)), true) => {
+            Mask::new(username, hostname, config.private.hide_username)
+        }
+        _ => Mask::default(),
+    };
+    let names = names.map(|(username, hostname)| (mask.apply(&username), mask.apply(&hostname)));
+    let show_header = config.header.show && !no_header;
+    let header = names.as_ref().filter(|_| show_header).map(|(username, hostname)| {
+        render::format_header(username, hostname, &config.header)
+    });
+
+    let mut out: Box<dyn Write> = match &output_path {
+        Some(path) => match File::create(path) {
+            Ok(file) => Box::new(BufWriter::new(file)),
+            Err(err) => {
+                eprintln!("risifetch: failed creating {}: {}", path.display(), err);
+                process::exit(1)
+            }
+        },
+        None => Box::new(io::stdout().lock()),
     };
 
-    // Shell
+    let mut timings: Vec<(&str, Duration)> = Vec::new();
+
+    // Every built-in field with its value or why it has none, for putting
+    // a fields list together
+    if list_fields {
+        let rows: Vec<(&str, Result<String, FetchError>)> = registry::FIELDS.iter()
+            .map(|field| (field.name, fetch_field(field, &ctx, &mask, &mut timings)))
+            .collect();
+
+        let result = if json {
+            render::write_field_list_json(&mut out, rows)
+        } else {
+            render::write_field_list(&mut out, rows)
+        };
+        result.unwrap_or_else(output_error);
+        if show_timings {
+            report_timings(&timings);
+        }
+        return;
+    }
 
-    if let Ok(value) = fields::get_shell() {
-        data_list.push(value);
-    };
+    // Just the values, one per line, for scripts. Asking for a field that
+    // can't be shown is an error.
+    if !requested.is_empty() {
+        let mut failed = false;
+
+        for name in &requested {
+            let value = fetch_named(name, &ctx, &mask, &mut timings).unwrap_or_else(|| unknown_field(name));
+            match value {
+                Ok(value) => writeln!(out, "{}", value).unwrap_or_else(output_error),
+                Err(err) => {
+                    eprintln!("risifetch: {}", err);
+                    failed = true;
+                }
+            }
+        }
 
-    // Uptime
+        out.flush().unwrap_or_else(output_error);
+        if show_timings {
+            report_timings(&timings);
+        }
+        if failed {
+            process::exit(2)
+        }
+        return;
+    }
 
-    if let Ok(value) = stat.uptime() {
-        data_list.push(fields::format_uptime(value));
-    };
+    // Variables for `eval "$(risifetch --env)"`, leaving out fields without
+    // a value
+    if env_output {
+        let mut values: Vec<(&str, String)> = Vec::new();
+        if let Some((username, hostname)) = &names {
+            values.push(("username", username.clone()));
+            values.push(("hostname", hostname.clone()));
+        }
+        for field in &registry::FIELDS {
+            values.extend(fetch_field(field, &ctx, &mask, &mut timings).map(|value| (field.name, value)).ok());
+        }
+        for name in &config.sysctl {
+            values.extend(fetch_sysctl(name, &ctx, &mask, &mut timings).map(|value| (sysctl::key(name), value)).ok());
+        }
+        for custom in &config.custom {
+            values.extend(fetch_custom(custom, &ctx, &mask, &mut timings)
+                .map(|lines| (custom.key.as_str(), lines.join("\n")))
+                .ok());
+        }
 
-    // Memory
+        render::write_env(&mut out, &values).unwrap_or_else(output_error);
+        if show_timings {
+            report_timings(&timings);
+        }
+        return;
+    }
 
-    if let Ok(value) = stat.memory() {
-        data_list.push(fields::format_memory(value));
-    };
+    if json {
+        let mut values: Vec<(&str, Value)> = Vec::new();
+        let mut errors: Vec<(&str, String)> = Vec::new();
+
+        for field in &registry::FIELDS {
+            match fetch_field(field, &ctx, &mask, &mut timings) {
+                // Scripts want the parts rather than the display string
+                Ok(_) if field.name == "kernel" => {
+                    values.push((field.name, json!(fields::get_kernel())));
+                }
+                Ok(value) if field.name == "uptime" => {
+                    let uptime = fields::boot_time(&sys, &stat).map(fields::get_uptime);
+                    values.push((field.name, uptime.map_or(Value::String(value), |uptime| json!(uptime))));
+                }
+                Ok(value) => values.push((field.name, Value::String(value))),
+                Err(err) => errors.push((field.name, err.to_string())),
+            }
+        }
+        for name in &config.sysctl {
+            match fetch_sysctl(name, &ctx, &mask, &mut timings) {
+                Ok(value) => values.push((sysctl::key(name), Value::String(value))),
+                Err(err) => errors.push((sysctl::key(name), err.to_string())),
+            }
+        }
+        for custom in &config.custom {
+            match fetch_custom(custom, &ctx, &mask, &mut timings) {
+                Ok(lines) => values.push((&custom.key, Value::String(lines.join("\n")))),
+                Err(err) => errors.push((&custom.key, err.to_string())),
+            }
+        }
+
+        render::write_json(&mut out, names, values, errors).unwrap_or_else(output_error);
+        if show_timings {
+            report_timings(&timings);
+        }
+        return;
+    }
+
+    if oneline {
+        let values = config.oneline.fields.iter()
+            .filter_map(|name| {
+                let value = fetch_named(name, &ctx, &mask, &mut timings).unwrap_or_else(|| unknown_field(name));
+                match value {
+                    Ok(value) => Some(value.replace('\n', " ")),
+                    Err(err) => {
+                        report_error(err);
+                        config.placeholder(name).map(str::to_string)
+                    }
+                }
+            })
+            .collect();
+
+        render::write_oneline(&mut out, header.map(|header| header.0), values, &config.oneline.separator)
+            .unwrap_or_else(output_error);
+        if show_timings {
+            report_timings(&timings);
+        }
+        return;
+    }
 
-    // Battery
+    let art: &[&str] = match (small, logo::RISI.small) {
+        (false, _) => logo::RISI.full,
+        (true, Some(small_art)) => small_art,
+        (true, None) => match config.small_fallback {
+            SmallFallback::Full => logo::RISI.full,
+            SmallFallback::None => &[],
+        },
+    };
 
-    if let Ok(value) = stat.battery_life() {
-        data_list.push(fields::format_battery(value));
+    let Selection { fields, probe } = match risifetch::select_fields(&config) {
+        Ok(selection) => selection,
+        Err(ConfigError::UnknownField(name)) => unknown_field(&name),
+        Err(err) => {
+            eprintln!("risifetch: {}", err);
+            process::exit(1)
+        }
+    };
+    let layout = Layout { mask: &mask, header, art, palette, fields };
+    let mut unavailable: Vec<&str> = Vec::new();
+
+    let result = match watch {
+        Some(interval) => terminal::watch(&mut out, interval, |frame| {
+            timings.clear();
+            unavailable.clear();
+            write_layout(frame, &ctx, &layout, &mut unavailable, &mut timings)
+        }),
+        None => write_layout(&mut out, &ctx, &layout, &mut unavailable, &mut timings),
     };
+    result.and_then(|_| out.flush()).unwrap_or_else(output_error);
+
+    if probe {
+        let available: Vec<&str> = layout.fields.iter()
+            .map(|field| field.name)
+            .filter(|name| !unavailable.contains(name))
+            .collect();
+        cache::save_fields(config.key(), &available);
+    }
 
-    println!();
-    print_left_to_right(ascii_tree, data_list);
-    println!();
+    if show_timings {
+        report_timings(&timings);
+    }
 }
 
-// Print two vectors of strings side to side
-fn print_left_to_right(left: Vec<String>, right: Vec<String>) {
-    let left_len = left.len();
-    let right_len = right.len();
-    let max_len = if left_len > right_len {left_len} else {right_len};
+// What write_layout draws, the same for every frame of --watch
+struct Layout<'a> {
+    mask: &'a Mask,
+    header: Option<(String, Option<String>)>,
+    art: &'a [&'a str],
+    palette: &'a [Color],
+    fields: Vec<&'a registry::Field>,
+}
 
-    for i in 0..max_len {
-        if i < left_len {
-            print!(" {}", left[i]);
+// The logo with the header and the fields beside it, noting the fields that
+// turned out to be unavailable
+fn write_layout<'a>(out: &mut impl Write,
+                    ctx: &Context<'a>,
+                    layout: &Layout<'a>,
+                    unavailable: &mut Vec<&'a str>,
+                    timings: &mut Vec<(&'a str, Duration)>) -> io::Result<()> {
+    let Layout { mask, art, palette, .. } = *layout;
+    let mut data_list: Vec<String> = Vec::new();
+    let vertical = ctx.config.layout.vertical;
+
+    let mut values: Vec<(&str, Vec<String>)> = Vec::new();
+    // Fields without a value keep their row when they have a placeholder and
+    // were asked for, built-in ones by the config's fields list
+    let placeholder = |name: &'a str, listed: bool| ctx.config.placeholder(name)
+        .filter(|_| listed)
+        .map(|placeholder| (name, vec![placeholder.to_string()]));
+    let listed = ctx.config.fields.is_some();
+
+    for field in &layout.fields {
+        match fetch_field(field, ctx, mask, timings) {
+            Ok(value) => values.push((field.name, value.lines().map(str::to_string).collect())),
+            Err(err) => {
+                match err {
+                    FetchError::NotAvailable(_) | FetchError::Disabled(_) => unavailable.push(field.name),
+                    err => report_error(err),
+                }
+                values.extend(placeholder(field.name, listed));
+            }
         }
-        if i < right_len {
-            print!(" {}", right[i]);
+    }
+    for name in &ctx.config.sysctl {
+        match fetch_sysctl(name, ctx, mask, timings) {
+            Ok(value) => values.push((sysctl::key(name), vec![value])),
+            Err(err) => {
+                report_error(err);
+                values.extend(placeholder(sysctl::key(name), true));
+            }
+        }
+    }
+    for custom in &ctx.config.custom {
+        match fetch_custom(custom, ctx, mask, timings) {
+            Ok(lines) => values.push((&custom.key, lines)),
+            Err(err) => {
+                report_error(err);
+                values.extend(placeholder(&custom.key, true));
+            }
         }
-        println!()
     }
+
+    // Only the keys that are shown, a long one of a missing field shouldn't
+    // push the values over
+    let labels = render::Labels::new(&ctx.config.labels, values.iter().map(|(name, _)| *name));
+
+    // Room left for the info block and for a value once the logo and the
+    // label are drawn
+    let columns = terminal::width();
+    let info_room = |art: &[&str]| {
+        let logo_width = match logo::width(art) {
+            0 => 0,
+            width => width + 1,
+        };
+        columns.map(|columns| columns.saturating_sub(logo_width + ctx.config.layout.gap))
+    };
+    let value_width = |art: &[&str]| info_room(art).map(|room| room.saturating_sub(labels.width()));
+
+    // A pane too narrow for the values next to the logo gets them without it,
+    // like --small for a distro without a compact logo
+    let fits = |art: &[&str]| value_width(art).is_none_or(|width| width >= render::MIN_VALUE_WIDTH);
+    let art: &[&str] = if !fits(art) && fits(&[]) { &[] } else { art };
+    let (info_room, value_width) = (info_room(art), value_width(art));
+    // Cut a long name short only when there's a logo for it to run into
+    let overflow = ctx.config.os_overflow
+        .unwrap_or(if art.is_empty() { Overflow::Wrap } else { Overflow::Truncate });
+    for (name, lines) in values.iter_mut() {
+        *lines = if *name == "os" {
+            render::fit_distro_name(&lines.join(" "), value_width, overflow)
+        } else {
+            render::wrap_value(std::mem::take(lines), value_width)
+        };
+    }
+
+    let align_width = match ctx.config.layout.values {
+        ValueAlign::Left => None,
+        ValueAlign::Right => values.iter()
+            .flat_map(|(_, lines)| lines.iter().map(|line| render::display_width(line)))
+            .max(),
+    };
+    let fields: Vec<String> = values.iter()
+        .flat_map(|(name, lines)| render::format_field(name, lines, align_width, &labels))
+        .collect();
+
+    // Start the fields a little lower so they sit beside the full logo's body,
+    // the other alignments take care of that by themselves
+    if art == logo::RISI.full && vertical == VerticalAlign::Top {
+        data_list.push(String::from(""));
+        data_list.push(String::from(""));
+    }
+
+    if let Some((user_host_name, separator)) = &layout.header {
+        let info_width = fields.iter().map(|line| render::display_width(line)).max().unwrap_or(0);
+        let separator = match ctx.config.header.separator_width {
+            SeparatorWidth::Header => separator.clone(),
+            width => {
+                let width = match width {
+                    SeparatorWidth::Terminal => info_room.unwrap_or(info_width),
+                    _ => info_width,
+                };
+                // Never shorter than user@host itself
+                render::format_separator(&ctx.config.header, width.max(render::display_width(user_host_name)))
+            }
+        };
+
+        data_list.push(user_host_name.clone());
+        data_list.extend(separator);
+    }
+    data_list.extend(fields);
+
+    let layout_config = &ctx.config.layout;
+    write!(out, "{}", "\n".repeat(layout_config.lines_before))?;
+    render::write_left_to_right(out,
+                                logo::paint(art, palette, colors::mode()),
+                                logo::width(art),
+                                data_list,
+                                layout_config.gap,
+                                vertical)?;
+    write!(out, "{}", "\n".repeat(layout_config.lines_after))
 }
 
-// Split a multi-line string into several ones separated by the newline
-fn split_by_newline(ascii_art: String) -> Vec<String> {
-    let mut split: Vec<String> = Vec::new();
-    let mut last_index = 0;
+// e.g. "risifetch: timings: os: 0.1ms, disk: 3.0ms"
+fn report_timings(timings: &[(&str, Duration)]) {
+    let report = timings.iter()
+        .map(|(name, time)| format!("{}: {:.1}ms", name, time.as_secs_f64() * 1000.0))
+        .collect::<Vec<_>>()
+        .join(", ");
 
-    let bytes = ascii_art.as_bytes();
+    eprintln!("risifetch: timings: {}", report);
+}
 
-    for (i, &item) in bytes.iter().enumerate() {
-        if item == b'\n' {
-            split.push(ascii_art[last_index..i].trim().to_string());
-            last_index = i;
-        }
+// A closed pipe (e.g. `risifetch | head`) just means nobody wants the rest
+fn output_error(err: io::Error) {
+    if err.kind() != io::ErrorKind::BrokenPipe {
+        eprintln!("risifetch: failed writing output: {}", err);
+        process::exit(1)
     }
+}
 
-    split
+// Unavailable fields are expected (e.g. no $SHELL), anything else is worth
+// telling the user about
+fn report_error(err: FetchError) {
+    if !matches!(err, FetchError::NotAvailable(..) | FetchError::Disabled(..)) {
+        eprintln!("risifetch: {}", err);
+    }
 }
 
 fn help_message() {
     let version = env!("CARGO_PKG_VERSION");
+    let mode = colors::mode();
     println!("Usage:");
     println!("  {bold}{cyan}risifetch{reset} [options]",
-            cyan = colors::cyan,
-            reset = colors::reset,
-            bold = colors::bold,
+            cyan = Color::Cyan.escape(mode),
+            reset = colors::reset(mode),
+            bold = colors::bold(mode),
             );
     println!();
     println!("OPTIONS");
     println!("  -h, --help     Display this help message");
+    println!("      --offline         Leave out the fields that would use the network");
     println!("  -k, --kernel-name     Display the kernel name");
+    println!("  -t, --theme <name>    Color theme: {}",
+             theme::THEMES.iter().map(|theme| theme.name).collect::<Vec<_>>().join(", "));
+    println!("  -r, --random-colors   Use a different random palette on each run");
+    println!("  -s, --small           Use the compact logo for narrow terminals");
+    println!("  -c, --config <file>   Read the config from this file, - for stdin");
+    println!("  -P, --profile <name>  Use the fields and theme of a profile in the config");
+    println!("  -o, --output <file>   Write the output to this file instead of stdout");
+    println!("  -f, --field <name>    Print only this field's value, can be repeated");
+    println!("  -1, --oneline         Print a compact single line");
+    println!("  -j, --json            Print the fields as JSON, with the reasons for missing ones");
+    println!("      --env             Print the fields as shell variables, e.g. RISIFETCH_OS='Arch Linux'");
+    println!("      --list-fields     Print every field with its value here, or why it has none");
+    println!("  -p, --private         Mask the hostname and IP for screenshots");
+    println!("  -w, --watch <secs>    Redraw the fields every few seconds until Ctrl-C");
+    println!("      --no-header       Leave out the user@host line and its separator");
+    println!("      --timings         Print how long each field took to stderr");
+    println!("  -v, --verbose         Print debugging details to stderr");
     println!();
     println!("risifetch {}", version);
     println!("Report bugs to https://github.com/risiOS/risifetch/issues");
@@ -169,3 +601,38 @@ fn invalid_option(option: String) {
     println!("Try 'risifetch --help' for more information.");
     process::exit(1)
 }
+
+// Seed for --random-colors, different on every run
+fn time_seed() -> u64 {
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+
+    now.as_secs() ^ u64::from(now.subsec_nanos()) ^ u64::from(process::id())
+}
+
+// Options like --theme need a value right after them
+fn option_value(option: &str, value: Option<String>) -> String {
+    value.unwrap_or_else(|| {
+        println!("Option '{}' requires a value", option);
+        println!("Try 'risifetch --help' for more information.");
+        process::exit(1)
+    })
+}
+
+fn invalid_value(option: &str, value: &str) {
+    println!("Invalid value '{}' for option '{}'", value, option);
+    println!("Try 'risifetch --help' for more information.");
+    process::exit(1)
+}
+
+fn unknown_theme(name: &str) -> ! {
+    println!("Unknown theme '{}'", name);
+    println!("Try 'risifetch --help' for the list of themes.");
+    process::exit(1)
+}
+
+fn unknown_field(name: &str) -> ! {
+    println!("Unknown field '{}'", name);
+    println!("Known fields: {}",
+             registry::FIELDS.iter().map(|field| field.name).collect::<Vec<_>>().join(", "));
+    process::exit(1)
+}
@@ -0,0 +1,137 @@
+use std::net::{IpAddr, Ipv6Addr};
+use nix::ifaddrs;
+use nix::sys::socket::SockAddr;
+use regex::Regex;
+use crate::config::IpFamily;
+use crate::error::FetchError;
+use crate::system::SystemInfo;
+
+// The interface carrying the default route, from /proc/net/route
+fn default_interface(sys: &dyn SystemInfo) -> Option<String> {
+    let routes = sys.read_file("/proc/net/route").ok()?;
+
+    routes.lines()
+        .skip(1)
+        .map(|line| line.split_whitespace().collect::<Vec<_>>())
+        .find(|columns| columns.get(1) == Some(&"00000000"))
+        .and_then(|columns| columns.first().map(|iface| iface.to_string()))
+}
+
+// The interface the network fields describe, the one named in the config or
+// else the default route's
+pub fn interface(sys: &dyn SystemInfo, configured: Option<&str>) -> Result<String, FetchError> {
+    match configured {
+        Some(name) => {
+            let exists = sys.read_dir("/sys/class/net")
+                .is_ok_and(|interfaces| interfaces.iter().any(|iface| iface == name));
+
+            if !exists {
+                return Err(FetchError::NoSuchInterface(name.to_string()));
+            }
+            Ok(name.to_string())
+        }
+        None => default_interface(sys)
+            .ok_or_else(|| FetchError::NotAvailable("default route".to_string())),
+    }
+}
+
+// The interface's addresses of the family, e.g. "192.168.1.20" or
+// "192.168.1.20, 2001:db8::20", straight from getifaddrs so no `ip` is needed.
+// Loopback and IPv6 link-local addresses are left out, every interface has one.
+pub fn get_local_ip(sys: &dyn SystemInfo, configured: Option<&str>, family: IpFamily) -> Result<String, FetchError> {
+    let iface = interface(sys, configured)?;
+    let addresses = ifaddrs::getifaddrs().map_err(|errno| FetchError::Sys("getifaddrs", errno.into()))?;
+
+    let mut ips: Vec<IpAddr> = addresses
+        .filter(|address| address.interface_name == iface)
+        .filter_map(|address| match address.address {
+            Some(SockAddr::Inet(inet)) => Some(inet.to_std().ip()),
+            _ => None,
+        })
+        .filter(|ip| !ip.is_loopback())
+        .filter(|ip| match ip {
+            IpAddr::V4(_) => family != IpFamily::Ipv6,
+            IpAddr::V6(ip) => family != IpFamily::Ipv4 && !is_link_local(ip),
+        })
+        .collect();
+    // IPv4 first
+    ips.sort_by_key(|ip| ip.is_ipv6());
+    ips.dedup();
+
+    if ips.is_empty() {
+        let family = match family {
+            IpFamily::Ipv4 => "IPv4 address",
+            IpFamily::Ipv6 => "IPv6 address",
+            IpFamily::Both => "IP address",
+        };
+        return Err(FetchError::NotAvailable(format!("{} on {}", family, iface)));
+    }
+    Ok(ips.iter().map(IpAddr::to_string).collect::<Vec<_>>().join(", "))
+}
+
+// fe80::/10
+fn is_link_local(ip: &Ipv6Addr) -> bool {
+    ip.segments()[0] & 0xffc0 == 0xfe80
+}
+
+// systemd-resolved's local stub, which says nothing about the real servers
+const RESOLVED_STUB: &str = "127.0.0.53";
+
+// The upstream servers systemd-resolved uses, from `resolvectl status`. Their
+// list may continue on the following lines, without a label of its own.
+fn resolved_servers(sys: &dyn SystemInfo) -> Vec<String> {
+    let Ok(status) = sys.command_output("resolvectl", &["status"]) else {
+        return Vec::new();
+    };
+    let label = Regex::new(r"^\s*[A-Za-z][A-Za-z ]*:(\s|$)").unwrap();
+
+    let mut servers: Vec<String> = Vec::new();
+    let mut in_list = false;
+    for line in status.lines() {
+        let addresses = match line.trim().strip_prefix("DNS Servers:") {
+            Some(addresses) => {
+                in_list = true;
+                addresses
+            }
+            None if in_list && !label.is_match(line) && !line.trim().is_empty() => line,
+            None => {
+                in_list = false;
+                continue;
+            }
+        };
+
+        // DNS over TLS servers come with "#name" after the address
+        for address in addresses.split_whitespace() {
+            let address = address.split('#').next().unwrap_or(address).to_string();
+            if !servers.contains(&address) {
+                servers.push(address);
+            }
+        }
+    }
+    servers
+}
+
+// The DNS servers from /etc/resolv.conf, e.g. "1.1.1.1, 8.8.8.8", or those
+// behind systemd-resolved's stub when that's all it lists
+pub fn get_dns(sys: &dyn SystemInfo) -> Result<String, FetchError> {
+    let resolv_conf = sys.read_required("/etc/resolv.conf")?;
+
+    let mut servers: Vec<String> = resolv_conf.lines()
+        .filter_map(|line| match line.split_whitespace().collect::<Vec<_>>()[..] {
+            ["nameserver", address, ..] => Some(address.to_string()),
+            _ => None,
+        })
+        .collect();
+
+    if servers == [RESOLVED_STUB] {
+        let upstream = resolved_servers(sys);
+        if !upstream.is_empty() {
+            servers = upstream;
+        }
+    }
+
+    if servers.is_empty() {
+        return Err(FetchError::NotAvailable("nameservers in /etc/resolv.conf".to_string()));
+    }
+    Ok(servers.join(", "))
+}
@@ -0,0 +1,84 @@
+use std::ffi::CStr;
+use std::sync::OnceLock;
+use nix::libc;
+
+// The separators numbers are written with
+#[derive(Debug)]
+struct Separators {
+    decimal: String,
+    // Between groups of three digits, none in the C locale
+    thousands: String,
+}
+
+// The C locale's unless use_locale() was called, so output stays easy to parse
+static SEPARATORS: OnceLock<Separators> = OnceLock::new();
+
+fn c_separators() -> Separators {
+    Separators { decimal: ".".to_string(), thousands: String::new() }
+}
+
+// The separators of the locale in LC_ALL, LC_NUMERIC or LANG
+fn locale_separators() -> Separators {
+    // SAFETY: setlocale and localeconv aren't thread-safe, this runs once at
+    // startup and puts the C locale back before returning. The strings
+    // localeconv points at are copied out before that.
+    unsafe {
+        if libc::setlocale(libc::LC_NUMERIC, c"".as_ptr()).is_null() {
+            return c_separators();
+        }
+        let conv = &*libc::localeconv();
+        let read = |ptr: *const libc::c_char| if ptr.is_null() {
+            String::new()
+        } else {
+            CStr::from_ptr(ptr).to_string_lossy().into_owned()
+        };
+        let separators = Separators { decimal: read(conv.decimal_point), thousands: read(conv.thousands_sep) };
+        libc::setlocale(libc::LC_NUMERIC, c"C".as_ptr());
+
+        if separators.decimal.is_empty() {
+            return c_separators();
+        }
+        separators
+    }
+}
+
+// Write numbers the way the system locale does, only has an effect before
+// the first number is formatted
+pub fn use_locale() {
+    let _ = SEPARATORS.set(locale_separators());
+}
+
+fn separators() -> &'static Separators {
+    SEPARATORS.get_or_init(c_separators)
+}
+
+// e.g. "12,345" with a thousands separator of ","
+pub fn integer(value: u64) -> String {
+    let digits = value.to_string();
+    let thousands = &separators().thousands;
+    if thousands.is_empty() {
+        return digits;
+    }
+
+    let mut grouped = String::new();
+    for (i, digit) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            grouped += thousands;
+        }
+        grouped.push(digit);
+    }
+    grouped
+}
+
+// e.g. "16,0" with `decimals` 1 and a decimal separator of ","
+pub fn decimal(value: f64, decimals: usize) -> String {
+    let formatted = format!("{:.*}", decimals, value);
+
+    match formatted.split_once('.') {
+        Some((whole, fraction)) => {
+            let whole = whole.parse().map(integer).unwrap_or_else(|_| whole.to_string());
+            format!("{}{}{}", whole, separators().decimal, fraction)
+        }
+        None => formatted,
+    }
+}
@@ -0,0 +1,132 @@
+use crate::colors::{self, Color};
+use crate::error::FetchError;
+use crate::numbers;
+use crate::theme;
+use crate::system::SystemInfo;
+
+// Every manager that can be counted, in the order they're reported
+pub const MANAGERS: [&str; 5] = ["dpkg", "rpm", "pacman", "flatpak", "snap"];
+
+// Installed entries of the dpkg status database
+fn count_dpkg(sys: &dyn SystemInfo) -> Option<usize> {
+    let status = sys.read_file("/var/lib/dpkg/status").ok()?;
+
+    Some(status.lines().filter(|line| *line == "Status: install ok installed").count())
+}
+
+// The rpm database is a binary format, so ask rpm itself
+fn count_rpm(sys: &dyn SystemInfo) -> Option<usize> {
+    let output = sys.command_output("rpm", &["-qa"]).ok()?;
+
+    Some(output.lines().count())
+}
+
+fn count_pacman(sys: &dyn SystemInfo) -> Option<usize> {
+    let entries = sys.read_dir("/var/lib/pacman/local").ok()?;
+
+    Some(entries.iter().filter(|name| *name != "ALPM_DB_VERSION").count())
+}
+
+// Apps installed system-wide and for the current user
+fn count_flatpak(sys: &dyn SystemInfo) -> Option<usize> {
+    let mut dirs = vec!["/var/lib/flatpak/app".to_string()];
+    if let Some(home) = sys.env_var("HOME") {
+        dirs.push(format!("{}/.local/share/flatpak/app", home.to_string_lossy()));
+    }
+
+    let apps: Vec<String> = dirs.iter()
+        .filter_map(|dir| sys.read_dir(dir).ok())
+        .flatten()
+        .collect();
+
+    if apps.is_empty() { None } else { Some(apps.len()) }
+}
+
+// snapd keeps every revision around as <name>_<revision>.snap
+fn count_snap(sys: &dyn SystemInfo) -> Option<usize> {
+    let mut names: Vec<String> = sys.read_dir("/var/lib/snapd/snaps").ok()?
+        .into_iter()
+        .filter(|file| file.ends_with(".snap"))
+        .filter_map(|file| file.rsplit_once('_').map(|(name, _)| name.to_string()))
+        .collect();
+    names.sort();
+    names.dedup();
+
+    Some(names.len())
+}
+
+// Per-manager counts, e.g. "1423 (dpkg), 37 (flatpak), 12 (snap)"
+pub fn get_packages(sys: &dyn SystemInfo, managers: &[String]) -> Result<String, FetchError> {
+    let counts: Vec<String> = MANAGERS.iter()
+        .filter(|manager| managers.iter().any(|wanted| wanted == *manager))
+        .filter_map(|manager| {
+            let count = match *manager {
+                "dpkg" => count_dpkg(sys),
+                "rpm" => count_rpm(sys),
+                "pacman" => count_pacman(sys),
+                "flatpak" => count_flatpak(sys),
+                _ => count_snap(sys),
+            };
+            count.filter(|count| *count > 0)
+                .map(|count| format!("{} ({})", numbers::integer(count as u64), manager))
+        })
+        .collect();
+
+    if counts.is_empty() {
+        return Err(FetchError::NotAvailable("packages".to_string()));
+    }
+    Ok(counts.join(", "))
+}
+
+// Pending updates from Arch's checkupdates, which exits with 2 when there are
+// none. It syncs a copy of the databases, so it needs the network.
+fn updates_pacman(sys: &dyn SystemInfo) -> Option<usize> {
+    sys.read_dir("/var/lib/pacman/local").ok()?;
+    let output = sys.command_output("sh", &["-c", "checkupdates || [ $? -eq 2 ]"]).ok()?;
+
+    Some(output.lines().filter(|line| !line.trim().is_empty()).count())
+}
+
+// From the package lists apt last downloaded
+fn updates_apt(sys: &dyn SystemInfo) -> Option<usize> {
+    sys.read_file("/var/lib/dpkg/status").ok()?;
+    let output = sys.command_output("apt", &["list", "--upgradable"]).ok()?;
+
+    Some(output.lines().filter(|line| line.contains("[upgradable from")).count())
+}
+
+// dnf exits with 100 when there are updates and 0 when there are none. Obsoleted
+// packages are listed again after the updates, under their own heading.
+fn updates_dnf(sys: &dyn SystemInfo) -> Option<usize> {
+    let script = "dnf -q check-update; status=$?; [ $status -eq 0 ] || [ $status -eq 100 ]";
+    let output = sys.command_output("sh", &["-c", script]).ok()?;
+
+    Some(output.lines()
+        .take_while(|line| !line.starts_with("Obsoleting"))
+        .filter(|line| line.split_whitespace().count() == 3)
+        .count())
+}
+
+// e.g. "42 updates (pacman)", in yellow unless there are none
+pub fn get_updates(sys: &dyn SystemInfo) -> Result<String, FetchError> {
+    let updates: Vec<String> = [("pacman", updates_pacman as fn(&dyn SystemInfo) -> Option<usize>),
+                                ("apt", updates_apt),
+                                ("dnf", updates_dnf)]
+        .iter()
+        .filter_map(|(manager, count)| {
+            let count = count(sys)?;
+            let text = format!("{} update{} ({})", numbers::integer(count as u64), if count == 1 { "" } else { "s" }, manager);
+
+            if count == 0 {
+                return Some(text);
+            }
+            let mode = colors::mode();
+            Some(format!("{}{}{}", Color::Yellow.escape(mode), text, theme::active().value.escape(mode)))
+        })
+        .collect();
+
+    if updates.is_empty() {
+        return Err(FetchError::NotAvailable("package updates".to_string()));
+    }
+    Ok(updates.join(", "))
+}
@@ -0,0 +1,250 @@
+use systemstat::Platform;
+use crate::config::Config;
+use crate::cpu;
+use crate::desktop;
+use crate::dmi;
+use crate::error::FetchError;
+use crate::fields;
+use crate::gpu;
+use crate::net;
+use crate::packages;
+use crate::system::{RealSystem, SystemInfo};
+use crate::terminal;
+use crate::virt;
+
+// Everything a field getter might need to look at
+pub struct Context<'a> {
+    pub sys: &'a dyn SystemInfo,
+    pub stat: &'a systemstat::System,
+    pub config: &'a Config,
+    pub show_kern_name: bool,
+}
+
+// A named field and how to fetch its value, which may span several lines
+pub struct Field {
+    pub name: &'static str,
+    fetch: fn(&Context) -> Result<String, FetchError>,
+}
+
+impl Field {
+    // Fields that use the network are left out offline before any
+    // connection is attempted, just like a field without a value
+    pub fn fetch(&self, ctx: &Context) -> Result<String, FetchError> {
+        if ctx.config.offline && NETWORK.contains(&self.name) {
+            return Err(FetchError::NotAvailable(format!("{} while offline", self.name)));
+        }
+
+        (self.fetch)(ctx)
+    }
+}
+
+// The fields that need the network
+static NETWORK: [&str; 1] = ["updates"];
+
+// Every field risifetch knows about, in display order
+pub static FIELDS: [Field; 35] = [
+    Field { name: "os", fetch: fetch_os },
+    Field { name: "os_family", fetch: |_| fields::get_os_family() },
+    Field { name: "host", fetch: |ctx| { skip_on_wsl(ctx, "host")?; dmi::get_host_model(ctx.sys) } },
+    Field { name: "chassis", fetch: |ctx| { skip_on_wsl(ctx, "chassis")?; dmi::get_chassis(ctx.sys) } },
+    Field { name: "mobo", fetch: |ctx| { skip_on_wsl(ctx, "mobo")?; dmi::get_motherboard(ctx.sys) } },
+    Field { name: "kernel", fetch: fetch_kernel },
+    Field { name: "secure boot", fetch: |ctx| fields::get_secure_boot(ctx.sys) },
+    Field { name: "virt", fetch: |ctx| virt::get_virtualization(ctx.sys) },
+    Field { name: "pkgs", fetch: |ctx| packages::get_packages(ctx.sys, &ctx.config.packages.managers) },
+    Field { name: "updates", fetch: fetch_updates },
+    Field { name: "failed units", fetch: |ctx| fields::get_failed_units(ctx.sys) },
+    Field { name: "shell", fetch: |ctx| fields::get_shell(ctx.sys, ctx.config.shell.source) },
+    Field { name: "term", fetch: |ctx| terminal::get_terminal(ctx.sys, &ctx.config.terminal.known) },
+    Field { name: "colors", fetch: |_| terminal::get_color_support() },
+    Field { name: "cursor", fetch: |ctx| desktop::get_cursor_theme(ctx.sys) },
+    Field { name: "windows", fetch: |ctx| desktop::get_window_count(ctx.sys) },
+    Field { name: "uptime", fetch: fetch_uptime },
+    Field { name: "idle", fetch: |ctx| fields::get_idle(ctx.sys) },
+    Field { name: "load", fetch: |ctx| fields::get_load(ctx.sys, ctx.config.precision.load) },
+    Field { name: "cpu", fetch: |ctx| cpu::get_cpu(ctx.sys, ctx.config.cpu.raw_name) },
+    Field { name: "cache", fetch: |ctx| cpu::get_cpu_cache(ctx.sys) },
+    Field { name: "cores", fetch: fetch_cores },
+    Field { name: "temp", fetch: |ctx| cpu::get_temperature(ctx.sys, ctx.config.temp_unit, ctx.config.precision.temp) },
+    Field { name: "memory", fetch: fetch_memory },
+    Field { name: "swap", fetch: |ctx| fields::get_swap(ctx.sys, ctx.config.swap_zram, &ctx.config.usage) },
+    Field { name: "gpu", fetch: fetch_gpu },
+    Field { name: "vram", fetch: |ctx| gpu::get_vram(ctx.sys) },
+    Field { name: "vulkan", fetch: fetch_vulkan },
+    Field { name: "disk", fetch: fetch_disk },
+    Field { name: "ip", fetch: |ctx| net::get_local_ip(ctx.sys, ctx.config.interface.as_deref(), ctx.config.ip_family) },
+    Field { name: "dns", fetch: |ctx| net::get_dns(ctx.sys) },
+    Field { name: "battery", fetch: fetch_battery },
+    Field { name: "health", fetch: |ctx| { skip_without_battery(ctx)?; fields::get_battery_health(ctx.sys) } },
+    Field { name: "cycles", fetch: |ctx| { skip_without_battery(ctx)?; fields::get_battery_cycles(ctx.sys) } },
+    Field { name: "risifetch", fetch: |_| fields::get_version() },
+];
+
+// Fields only shown next to the logo when the config's fields list has them,
+// they're in JSON and available to --field and --oneline regardless
+pub const ON_REQUEST: [&str; 4] = ["os_family", "colors", "idle", "risifetch"];
+
+// Fields whose whole value --private hides, as it identifies the machine
+pub const SENSITIVE: [&str; 1] = ["ip"];
+
+pub fn find(name: &str) -> Option<&'static Field> {
+    FIELDS.iter().find(|field| field.name == name)
+}
+
+// Under WSL the DMI tables and the PCI bus describe Hyper-V's virtual
+// hardware rather than the machine, so those fields are left out
+fn skip_on_wsl(ctx: &Context, name: &str) -> Result<(), FetchError> {
+    match virt::wsl_version(ctx.sys) {
+        Some(_) => Err(FetchError::NotAvailable(format!("{} under WSL", name))),
+        None => Ok(()),
+    }
+}
+
+// e.g. "Ubuntu 22.04.3 LTS on WSL2"
+fn fetch_os(ctx: &Context) -> Result<String, FetchError> {
+    let distro = fields::get_distro_name(ctx.sys, ctx.config.os_short_name)?;
+
+    Ok(match virt::wsl_version(ctx.sys) {
+        Some(version) => format!("{} on WSL{}", distro, version),
+        None => distro,
+    })
+}
+
+fn fetch_kernel(ctx: &Context) -> Result<String, FetchError> {
+    Ok(fields::format_kernel(&fields::get_kernel(), ctx.show_kern_name, ctx.config.kernel_short))
+}
+
+fn fetch_uptime(ctx: &Context) -> Result<String, FetchError> {
+    let uptime = fields::get_uptime(fields::boot_time(ctx.sys, ctx.stat)?);
+
+    Ok(fields::format_uptime(&uptime, ctx.config.uptime_format))
+}
+
+fn fetch_memory(ctx: &Context) -> Result<String, FetchError> {
+    if ctx.config.memory_detail {
+        fields::get_memory_detail(ctx.sys, &ctx.config.usage)
+    } else {
+        fields::get_memory(ctx.sys, ctx.config.memory_used, &ctx.config.usage)
+    }
+}
+
+fn fetch_gpu(ctx: &Context) -> Result<String, FetchError> {
+    skip_on_wsl(ctx, "gpu")?;
+
+    Ok(gpu::format_gpus(&gpu::get_gpus(ctx.sys)?, ctx.config.gpu.multiple))
+}
+
+// Sampling /proc/stat takes a while, so it's opt-in
+fn fetch_cores(ctx: &Context) -> Result<String, FetchError> {
+    if !ctx.config.cpu.cores {
+        return Err(FetchError::Disabled("cpu.cores"));
+    }
+
+    cpu::get_core_usage(ctx.sys)
+}
+
+// Checking for updates can take seconds and go over the network, so it's
+// opt-in, has a timeout of its own and is skipped offline
+fn fetch_updates(ctx: &Context) -> Result<String, FetchError> {
+    let packages = &ctx.config.packages;
+    if !packages.updates {
+        return Err(FetchError::Disabled("packages.updates"));
+    }
+
+    packages::get_updates(&RealSystem { command_timeout: ctx.config.timeout_or_default(packages.updates_timeout_ms) })
+}
+
+// Spawning vulkaninfo is slow enough to be opt-in
+fn fetch_vulkan(ctx: &Context) -> Result<String, FetchError> {
+    if !ctx.config.gpu.vulkan {
+        return Err(FetchError::Disabled("gpu.vulkan"));
+    }
+
+    gpu::get_vulkan(ctx.sys)
+}
+
+// Inodes go on a second line, out of the same statvfs call
+fn fetch_disk(ctx: &Context) -> Result<String, FetchError> {
+    let disk = &ctx.config.disk;
+    let fs = ctx.stat.mount_at("/").map_err(|err| FetchError::Sys("statvfs of /", err))?;
+
+    let inodes = if disk.inodes { fields::format_inodes(&fs) } else { None };
+    let usage = fields::format_disk(fs, disk.warn_at, disk.warn_color, &ctx.config.usage);
+
+    Ok(match inodes {
+        Some(inodes) => format!("{}\n{}", usage, inodes),
+        None => usage,
+    })
+}
+
+// Desktops and servers don't have a battery to look for
+fn skip_without_battery(ctx: &Context) -> Result<(), FetchError> {
+    match dmi::chassis(ctx.sys) {
+        Some(chassis) if !chassis.is_portable() => Err(FetchError::NotAvailable("battery".to_string())),
+        _ => Ok(()),
+    }
+}
+
+// Desktops have no battery, which isn't worth complaining about
+fn fetch_battery(ctx: &Context) -> Result<String, FetchError> {
+    skip_without_battery(ctx)?;
+    let battery = ctx.stat.battery_life()
+        .map_err(|_| FetchError::NotAvailable("battery".to_string()))?;
+
+    fields::format_battery(ctx.sys, battery, ctx.config.battery.remaining_time, ctx.config.uptime_format)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::system::mock::MockSystem;
+
+    // A sandbox without /proc (or /sys) loses those fields but nothing else
+    #[test]
+    fn fields_without_proc() {
+        let sys = MockSystem::default();
+        let stat = systemstat::System::new();
+        let config = Config { memory_detail: true, ..Config::default() };
+        let ctx = Context { sys: &sys, stat: &stat, config: &config, show_kern_name: false };
+
+        let values: Vec<(&str, Result<String, FetchError>)> = FIELDS.iter()
+            .map(|field| (field.name, field.fetch(&ctx)))
+            .collect();
+        let value = |name: &str| &values.iter().find(|(field, _)| *field == name).unwrap().1;
+
+        for name in ["memory", "swap", "cpu", "temp", "gpu", "health"] {
+            assert!(value(name).is_err(), "{} without /proc or /sys", name);
+        }
+        for name in ["os", "kernel"] {
+            assert!(value(name).is_ok(), "{} without /proc or /sys", name);
+        }
+    }
+
+    // Offline, the updates check isn't even started
+    #[test]
+    fn updates_offline() {
+        let sys = MockSystem::default();
+        let stat = systemstat::System::new();
+        let mut config = Config { offline: true, ..Config::default() };
+        config.packages.updates = true;
+        let ctx = Context { sys: &sys, stat: &stat, config: &config, show_kern_name: false };
+
+        let updates = find("updates").unwrap().fetch(&ctx);
+        assert!(matches!(updates, Err(FetchError::NotAvailable(name)) if name == "updates while offline"));
+    }
+
+    // Opt-in fields left off say how to turn them on, instead of looking
+    // unavailable
+    #[test]
+    fn opt_in_fields_disabled() {
+        let sys = MockSystem::default();
+        let stat = systemstat::System::new();
+        let config = Config::default();
+        let ctx = Context { sys: &sys, stat: &stat, config: &config, show_kern_name: false };
+
+        for (name, setting) in [("cores", "cpu.cores"), ("vulkan", "gpu.vulkan"), ("updates", "packages.updates")] {
+            let err = find(name).unwrap().fetch(&ctx).unwrap_err();
+            assert_eq!(err.to_string(), format!("disabled (enable with {})", setting));
+        }
+    }
+}
@@ -0,0 +1,547 @@
+use std::io::{self, Write};
+use regex::Regex;
+use serde_json::{Map, Value};
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+use crate::colors;
+use crate::config::{HeaderConfig, LabelCase, LabelConfig, Overflow, VerticalAlign};
+use crate::error::FetchError;
+use crate::registry;
+use crate::theme;
+
+// How the keys in front of the values are drawn for one layout
+pub struct Labels<'a> {
+    config: &'a LabelConfig,
+    // The longest key shown, so the values line up
+    key_width: usize,
+}
+
+impl<'a> Labels<'a> {
+    pub fn new<'k>(config: &'a LabelConfig, keys: impl Iterator<Item = &'k str>) -> Labels<'a> {
+        let mut labels = Labels { config, key_width: 0 };
+        labels.key_width = keys.map(|key| display_width(&labels.text(key)))
+            .max()
+            .unwrap_or(0)
+            .max(7);
+
+        labels
+    }
+
+    // The key as it's shown, renamed in the config or in its case
+    fn text(&self, key: &str) -> String {
+        match self.config.names.get(key) {
+            Some(name) => name.clone(),
+            None => apply_case(key, self.config.case),
+        }
+    }
+
+    // The bullet and space in front of every key, nothing for an empty bullet
+    fn bullet(&self) -> String {
+        if self.config.bullet.is_empty() {
+            String::new()
+        } else {
+            format!("{} ", self.config.bullet)
+        }
+    }
+
+    // Columns taken by the bullet and padded key in front of every value
+    pub fn width(&self) -> usize {
+        display_width(&self.bullet()) + self.key_width + 1
+    }
+}
+
+fn apply_case(key: &str, case: LabelCase) -> String {
+    match case {
+        LabelCase::Lower => key.to_string(),
+        LabelCase::Upper => key.to_uppercase(),
+        LabelCase::Title => {
+            let mut chars = key.chars();
+            match chars.next() {
+                Some(first) => first.to_uppercase().chain(chars).collect(),
+                None => String::new(),
+            }
+        }
+    }
+}
+
+pub fn format_data(key: &str, value: &str, labels: &Labels) -> String {
+    let mode = colors::mode();
+    let theme = theme::active();
+    // Padded by hand, a format width counts chars and not the columns they take
+    let key = labels.text(key);
+    let padding = " ".repeat(labels.key_width.saturating_sub(display_width(&key)));
+
+    format!("{accent}{bullet}{label}{bold}{key}{padding}{reset} {color}{value}",
+            value = value,
+            bullet = labels.bullet(),
+            accent = theme.accent.escape(mode),
+            label = theme.label.escape(mode),
+            color = theme.value.escape(mode),
+            bold = colors::bold(mode),
+            reset = colors::reset(mode),
+            )
+}
+
+// The colored user@host line and the separator drawn under it, if any
+pub fn format_header(username: &str, hostname: &str, config: &HeaderConfig) -> (String, Option<String>) {
+    let mode = colors::mode();
+    let theme = theme::active();
+    let user_color = config.username_color.unwrap_or(theme.accent).escape(mode);
+    let at_color = config.at_color.unwrap_or(theme.value).escape(mode);
+    let host_color = config.hostname_color.unwrap_or(theme.accent).escape(mode);
+
+    let user_host_name = format!("{user_color}{bold}{user}{reset}{bold}{at_color}@{reset}{bold}{host_color}{host}{reset}",
+                                 user = username,
+                                 host = hostname,
+                                 user_color = user_color,
+                                 at_color = at_color,
+                                 host_color = host_color,
+                                 bold = colors::bold(mode),
+                                 reset = colors::reset(mode),
+                                 );
+
+    // Separator
+    // format: username width + @ (1) + hostname width
+    let user_host_name_len = display_width(username) + 1 + display_width(hostname);
+
+    (user_host_name, format_separator(config, user_host_name_len))
+}
+
+// The line under user@host, filled with as many copies of the glyph as fit in
+// `width` columns, a wide glyph like "═" counts once per column
+pub fn format_separator(config: &HeaderConfig, width: usize) -> Option<String> {
+    let glyph_width = display_width(&config.separator);
+    if glyph_width == 0 {
+        return None;
+    }
+
+    let mode = colors::mode();
+    let color = config.separator_color.unwrap_or(theme::active().separator);
+
+    Some(format!("{color}{line}{reset}",
+                 color = color.escape(mode),
+                 line = config.separator.repeat(width / glyph_width),
+                 reset = colors::reset(mode)))
+}
+
+// Shown instead of a value --private hides
+pub const HIDDEN: &str = "<hidden>";
+
+// Hides identifying details for --private, so the output is safe to post.
+// An empty mask leaves everything as is.
+#[derive(Default)]
+pub struct Mask {
+    replacements: Vec<(Regex, &'static str)>,
+    hide_sensitive: bool,
+}
+
+impl Mask {
+    pub fn new(username: &str, hostname: &str, hide_username: bool) -> Mask {
+        let mut names = vec![(hostname, "host")];
+        if hide_username {
+            names.push((username, "user"));
+        }
+
+        let replacements = names.into_iter()
+            .filter(|(name, _)| !name.is_empty())
+            .map(|(name, placeholder)| {
+                let name = Regex::new(&format!(r"\b{}\b", regex::escape(name))).unwrap();
+                (name, placeholder)
+            })
+            .collect();
+
+        Mask { replacements, hide_sensitive: true }
+    }
+
+    // A field's value as it may be shown. The ones in registry::SENSITIVE
+    // keep their row, so the layout looks the same, but not their value.
+    pub fn apply_field(&self, field: &str, value: &str) -> String {
+        if self.hide_sensitive && registry::SENSITIVE.contains(&field) {
+            return HIDDEN.to_string();
+        }
+        self.apply(value)
+    }
+
+    pub fn apply(&self, text: &str) -> String {
+        let mut masked = text.to_string();
+
+        for (name, placeholder) in &self.replacements {
+            masked = name.replace_all(&masked, *placeholder).into_owned();
+        }
+        masked
+    }
+}
+
+// How many terminal columns a string takes up, wide CJK characters count
+// twice, combining marks and escape codes not at all
+pub fn display_width(text: &str) -> usize {
+    UnicodeWidthStr::width(strip_ansi(text).as_str())
+}
+
+// The text without escape sequences and control characters other than
+// newlines and tabs, for output of other programs that may move the cursor or
+// clear the screen
+pub fn strip_ansi(text: &str) -> String {
+    filter_escapes(text, false)
+}
+
+// Ditto, but colors are kept, for commands asked to match the theme
+pub fn strip_non_color_escapes(text: &str) -> String {
+    filter_escapes(text, true)
+}
+
+fn filter_escapes(text: &str, keep_colors: bool) -> String {
+    let mut filtered = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\x1b' => match chars.next() {
+                // CSI, e.g. "\x1b[1;31m", up to the final byte
+                Some('[') => {
+                    let mut sequence = String::from("\x1b[");
+                    for c in chars.by_ref() {
+                        sequence.push(c);
+                        if ('\x40'..='\x7e').contains(&c) {
+                            break;
+                        }
+                    }
+                    if keep_colors && sequence.ends_with('m') {
+                        filtered.push_str(&sequence);
+                    }
+                }
+                // OSC, e.g. a window title, up to BEL or ESC \
+                Some(']') => {
+                    while let Some(c) = chars.next() {
+                        if c == '\x07' || (c == '\x1b' && chars.next_if_eq(&'\\').is_some()) {
+                            break;
+                        }
+                    }
+                }
+                // e.g. "\x1b(B", intermediate bytes and a final one
+                Some(c) if ('\x20'..='\x2f').contains(&c) => {
+                    while chars.next_if(|c| ('\x20'..='\x2f').contains(c)).is_some() {}
+                    chars.next();
+                }
+                // Anything else is ESC and a single character
+                _ => {}
+            },
+            '\n' | '\t' => filtered.push(c),
+            c if c.is_control() => {}
+            c => filtered.push(c),
+        }
+    }
+    filtered
+}
+
+// Cut a string down to `width` columns, marking the cut with an ellipsis
+fn truncate_to_width(text: &str, width: usize) -> String {
+    if display_width(text) <= width {
+        return text.to_string();
+    }
+
+    let mut truncated = String::new();
+    let mut used = 1; // Room for the ellipsis
+
+    for c in text.chars() {
+        used += c.width().unwrap_or(0);
+        if used > width {
+            break;
+        }
+        truncated.push(c);
+    }
+
+    truncated.trim_end().to_string() + "…"
+}
+
+// Break a string into lines of at most `width` columns, on spaces when possible
+fn wrap_to_width(text: &str, width: usize) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    let mut line = String::new();
+
+    for word in text.split_whitespace() {
+        let needed = if line.is_empty() { 0 } else { 1 } + display_width(word);
+
+        if !line.is_empty() && display_width(&line) + needed > width {
+            lines.push(std::mem::take(&mut line));
+        }
+        if !line.is_empty() {
+            line.push(' ');
+        }
+
+        // A single word wider than the line gets split wherever it has to
+        for c in word.chars() {
+            if !line.is_empty() && display_width(&line) + c.width().unwrap_or(0) > width {
+                lines.push(std::mem::take(&mut line));
+            }
+            line.push(c);
+        }
+    }
+    lines.push(line);
+
+    lines
+}
+
+// Below this many columns values are left as they are, wrapping or cutting
+// them any shorter leaves nothing readable
+pub const MIN_VALUE_WIDTH: usize = 15;
+
+// Some distros put version, codename and edition in the name, so keep it
+// within `max_width` columns when there's a limit
+pub fn fit_distro_name(name: &str, max_width: Option<usize>, overflow: Overflow) -> Vec<String> {
+    let max_width = match max_width {
+        Some(max_width) if max_width >= MIN_VALUE_WIDTH => max_width,
+        _ => return vec![name.to_string()],
+    };
+
+    match overflow {
+        Overflow::Truncate => vec![truncate_to_width(name, max_width)],
+        Overflow::Wrap => wrap_to_width(name, max_width),
+    }
+}
+
+// Break the lines of a value that don't fit in `max_width` columns, so the
+// rest continues under the value column instead of under the logo. Colored
+// lines are left as they are, breaking them could split an escape code.
+pub fn wrap_value(lines: Vec<String>, max_width: Option<usize>) -> Vec<String> {
+    let max_width = match max_width {
+        Some(max_width) if max_width >= MIN_VALUE_WIDTH => max_width,
+        _ => return lines,
+    };
+
+    lines.into_iter()
+        .flat_map(|line| {
+            if line.contains('\x1b') || display_width(&line) <= max_width {
+                vec![line]
+            } else {
+                wrap_to_width(&line, max_width)
+            }
+        })
+        .collect()
+}
+
+// A field's value with its label on the first line and the rest indented to
+// the value column. With `align_width` the lines are right-aligned to it.
+pub fn format_field(key: &str,
+                    lines: &[String],
+                    align_width: Option<usize>,
+                    labels: &Labels) -> Vec<String> {
+    let mode = colors::mode();
+    let color = theme::active().value.escape(mode);
+    let align = |line: &str| match align_width {
+        Some(width) => format!("{:pad$}{}", "", line, pad = width.saturating_sub(display_width(line))),
+        None => line.to_string(),
+    };
+
+    let (first, rest) = lines.split_first().map_or(("", &[][..]), |(first, rest)| (first.as_str(), rest));
+
+    let mut formatted = vec![format_data(key, &align(first), labels)];
+    for line in rest {
+        formatted.push(format!("{}{:indent$}{}", color, "", align(line), indent = labels.width()));
+    }
+    formatted
+}
+
+// Write two vectors of strings side to side, `gap` columns apart, the right
+// one at the top, in the middle or at the bottom of the left one
+pub fn write_left_to_right(out: &mut impl Write,
+                           left: Vec<String>,
+                           left_width: usize,
+                           right: Vec<String>,
+                           gap: usize,
+                           vertical: VerticalAlign) -> io::Result<()> {
+    let offset = match vertical {
+        VerticalAlign::Top => 0,
+        VerticalAlign::Center => left.len().saturating_sub(right.len()) / 2,
+        VerticalAlign::Bottom => left.len().saturating_sub(right.len()),
+    };
+    let left_len = left.len();
+    let right_len = right.len() + offset;
+    let max_len = if left_len > right_len {left_len} else {right_len};
+
+    for i in 0..max_len {
+        if i < left_len {
+            write!(out, " {}", left[i])?;
+        } else if left_len > 0 {
+            // Keep the right column in place once the left one runs out
+            write!(out, " {:width$}", "", width = left_width)?;
+        }
+        if i >= offset && i < right_len {
+            write!(out, "{:gap$}{}", "", right[i - offset], gap = gap)?;
+        }
+        writeln!(out)?;
+    }
+
+    Ok(())
+}
+
+// Everything on a single line for prompts and status bars, e.g.
+// "user@host | Arch Linux | 5.15.0"
+pub fn write_oneline(out: &mut impl Write,
+                     header: Option<String>,
+                     values: Vec<String>,
+                     separator: &str) -> io::Result<()> {
+    let mode = colors::mode();
+    let theme = theme::active();
+
+    let mut parts: Vec<String> = header.into_iter().collect();
+    parts.extend(values.iter().map(|value| {
+        format!("{}{}{}", theme.value.escape(mode), value, colors::reset(mode))
+    }));
+
+    let separator = format!("{}{}{}", theme.separator.escape(mode), separator, colors::reset(mode));
+
+    writeln!(out, "{}", parts.join(&separator))
+}
+
+// Machine-readable output, e.g.
+// {"username": "risi", "hostname": "desktop", "fields": {"os": "risiOS 36",
+//  "kernel": {"sysname": "Linux", "release": "6.5.6", "machine": "x86_64"},
+//  "uptime": {"secs": 4560, "days": 0, "hours": 1, "minutes": 16}, ...},
+//  "errors": {"battery": "battery is not available"}}
+// Fields keep the display order so the output diffs cleanly between runs.
+pub fn write_json(out: &mut impl Write,
+                  names: Option<(String, String)>,
+                  values: Vec<(&str, Value)>,
+                  errors: Vec<(&str, String)>) -> io::Result<()> {
+    let object = |entries: Vec<(&str, Value)>| entries.into_iter()
+        .map(|(key, value)| (key.to_string(), value))
+        .collect::<Map<String, Value>>();
+
+    let mut json = Map::new();
+    if let Some((username, hostname)) = names {
+        json.insert("username".to_string(), Value::String(username));
+        json.insert("hostname".to_string(), Value::String(hostname));
+    }
+    json.insert("fields".to_string(), Value::Object(object(values)));
+    let errors = errors.into_iter().map(|(key, err)| (key, Value::String(err))).collect();
+    json.insert("errors".to_string(), Value::Object(object(errors)));
+
+    serde_json::to_writer_pretty(&mut *out, &json)?;
+    writeln!(out)
+}
+
+// A table of fields for --list-fields, e.g.
+// FIELD    AVAILABLE  VALUE
+// os       yes        Arch Linux
+// battery  no         battery is not available
+// cores    disabled   disabled (enable with cpu.cores)
+pub fn write_field_list(out: &mut impl Write, rows: Vec<(&str, Result<String, FetchError>)>) -> io::Result<()> {
+    let name_width = rows.iter().map(|(name, _)| display_width(name)).max().unwrap_or(0).max(5);
+
+    writeln!(out, "{:name_width$}  AVAILABLE  VALUE", "FIELD", name_width = name_width)?;
+    for (name, value) in rows {
+        let (available, text) = match value {
+            Ok(value) => ("yes", value.replace('\n', " ")),
+            Err(err @ FetchError::Disabled(_)) => ("disabled", err.to_string()),
+            Err(err) => ("no", err.to_string()),
+        };
+        let pad = name_width.saturating_sub(display_width(name));
+        writeln!(out, "{}{:pad$}  {:9}  {}", name, "", available, text, pad = pad)?;
+    }
+    Ok(())
+}
+
+// Ditto with --json, e.g.
+// [{"name": "os", "available": true, "value": "Arch Linux"},
+//  {"name": "battery", "available": false, "reason": "battery is not available"},
+//  {"name": "cores", "available": false, "disabled": true, "reason": "disabled (enable with cpu.cores)"}]
+pub fn write_field_list_json(out: &mut impl Write, rows: Vec<(&str, Result<String, FetchError>)>) -> io::Result<()> {
+    let rows: Vec<Value> = rows.into_iter()
+        .map(|(name, value)| match value {
+            Ok(value) => serde_json::json!({ "name": name, "available": true, "value": value }),
+            Err(err) => serde_json::json!({
+                "name": name,
+                "available": false,
+                "disabled": matches!(err, FetchError::Disabled(_)),
+                "reason": err.to_string(),
+            }),
+        })
+        .collect();
+
+    serde_json::to_writer_pretty(&mut *out, &rows)?;
+    writeln!(out)
+}
+
+// Shell variables to eval, e.g. RISIFETCH_OS='Arch Linux'. Single quotes keep
+// everything literal, newlines included, except single quotes themselves.
+pub fn write_env(out: &mut impl Write, values: &[(&str, String)]) -> io::Result<()> {
+    for (key, value) in values {
+        let name: String = key.chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_uppercase() } else { '_' })
+            .collect();
+
+        writeln!(out, "RISIFETCH_{}='{}'", name, value.replace('\'', r"'\''"))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn private_hides_ip_but_keeps_the_field() {
+        let mask = Mask::new("risi", "risibox", false);
+
+        assert_eq!(mask.apply_field("ip", "192.168.1.20"), HIDDEN);
+        assert_eq!(mask.apply_field("os", "risiOS on risibox"), "risiOS on host");
+        assert_eq!(Mask::default().apply_field("ip", "192.168.1.20"), "192.168.1.20");
+    }
+
+    #[test]
+    fn display_width_counts_columns_not_bytes() {
+        assert_eq!(display_width("risi@desktop"), 12);
+        assert_eq!(display_width("risi@straße"), 11);
+        assert_eq!(display_width("risi@cafe\u{301}"), 9);
+        assert_eq!(display_width("risi@服务器"), 11);
+    }
+
+    #[test]
+    fn values_line_up_after_wide_labels() {
+        let mut config = LabelConfig::default();
+        config.names.insert("os".to_string(), "系统".to_string());
+        config.names.insert("kernel".to_string(), "カーネルです".to_string());
+        let labels = Labels::new(&config, ["os", "kernel", "uptime"].into_iter());
+
+        for key in ["os", "kernel", "uptime"] {
+            let line = strip_ansi(&format_data(key, "value", &labels));
+            let key_part = line.strip_suffix("value").unwrap();
+            assert_eq!(display_width(key_part), labels.width(), "{line}");
+        }
+    }
+
+    #[test]
+    fn separator_matches_a_wide_hostname() {
+        let (_, separator) = format_header("risi", "服务器", &HeaderConfig::default());
+
+        // One dash per column, the CJK hostname takes 6 of them in 9 bytes
+        assert_eq!(strip_ansi(&separator.unwrap()), "-".repeat(11));
+    }
+
+    #[test]
+    fn fit_distro_name_keeps_narrow_names() {
+        let name = "Fedora Linux 39 (Workstation Edition)";
+
+        assert_eq!(fit_distro_name(name, Some(3), Overflow::Truncate), [name]);
+        assert_eq!(fit_distro_name(name, Some(3), Overflow::Wrap), [name]);
+        assert_eq!(fit_distro_name(name, Some(20), Overflow::Truncate), ["Fedora Linux 39 (Wo…"]);
+    }
+
+    #[test]
+    fn format_field_without_lines() {
+        let config = LabelConfig::default();
+        let labels = Labels::new(&config, ["cpu"].into_iter());
+
+        assert_eq!(format_field("cpu", &[], None, &labels).len(), 1);
+        assert_eq!(format_field("cpu", &[], Some(10), &labels).len(), 1);
+    }
+
+    #[test]
+    fn strip_ansi_removes_escapes_and_controls() {
+        let output = "\x1b]0;title\x07\x1b[1;32mGeForce\x1b[0m RTX\r\x1b[2K 3090\x1b(B\x08\nnext";
+
+        assert_eq!(strip_ansi(output), "GeForce RTX 3090\nnext");
+        assert_eq!(strip_non_color_escapes(output), "\x1b[1;32mGeForce\x1b[0m RTX 3090\nnext");
+        assert_eq!(display_width("\x1b[31m41%\x1b[0m"), 3);
+    }
+}
@@ -0,0 +1,46 @@
+use crate::error::FetchError;
+use crate::system::SystemInfo;
+
+// The path of a tunable under /proc/sys, given like sysctl(8) takes it, e.g.
+// "vm.swappiness", or as a path for names with dots in them, e.g.
+// "net/ipv4/conf/eth0.100/forwarding"
+fn path(name: &str) -> String {
+    let name = name.trim_start_matches("/proc/sys/");
+
+    if name.contains('/') {
+        format!("/proc/sys/{}", name)
+    } else {
+        format!("/proc/sys/{}", name.replace('.', "/"))
+    }
+}
+
+// What a tunable is shown as, its whole name so that e.g. the forwarding of
+// IPv4 and IPv6 stay apart in --json and --env
+pub fn key(name: &str) -> &str {
+    name.trim_start_matches("/proc/sys/")
+}
+
+// The value of a tunable, with runs of whitespace between several values
+// squeezed, e.g. "4096 131072 6291456"
+pub fn fetch(sys: &dyn SystemInfo, name: &str) -> Result<String, FetchError> {
+    let unavailable = || FetchError::NotAvailable(format!("sysctl {}", name));
+    let value = sys.read_file(&path(name)).map_err(|_| unavailable())?;
+
+    let value = value.split_whitespace().collect::<Vec<_>>().join(" ");
+    if value.is_empty() {
+        return Err(unavailable());
+    }
+    Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keys_of_tunables_with_the_same_last_part_differ() {
+        assert_eq!(key("net.ipv4.conf.all.forwarding"), "net.ipv4.conf.all.forwarding");
+        assert_ne!(key("net.ipv4.conf.all.forwarding"), key("net.ipv6.conf.all.forwarding"));
+        assert_eq!(key("/proc/sys/net/ipv4/conf/eth0.100/forwarding"), "net/ipv4/conf/eth0.100/forwarding");
+    }
+}
@@ -0,0 +1,229 @@
+use std::env;
+use std::ffi::OsString;
+use std::fs;
+use std::io::{self, Read};
+use std::os::unix::process::CommandExt;
+use std::path::PathBuf;
+use std::process::{Child, Command, Stdio};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::thread;
+use std::time::{Duration, Instant};
+use nix::sys::signal::{self, Signal};
+use nix::unistd::Pid;
+use crate::error::FetchError;
+use crate::render;
+
+// Where the field getters get their raw data from, so tests can swap the
+// real machine for canned files and variables
+pub trait SystemInfo {
+    fn read_file(&self, path: &str) -> io::Result<String>;
+    fn env_var(&self, name: &str) -> Option<OsString>;
+    // Names of the entries in a directory
+    fn read_dir(&self, path: &str) -> io::Result<Vec<String>>;
+    // Standard output of a command that exited successfully
+    fn command_output(&self, program: &str, args: &[&str]) -> io::Result<String>;
+
+    // read_file for the files a field can't do without, naming the file
+    // when it can't be read
+    fn read_required(&self, path: &str) -> Result<String, FetchError> {
+        self.read_file(path).map_err(|err| FetchError::Io(PathBuf::from(path), err))
+    }
+}
+
+// The machine risifetch is running on. Commands that take longer than
+// `command_timeout` are killed, so a stuck one can't hold up the fetch.
+pub struct RealSystem {
+    pub command_timeout: Duration,
+}
+
+impl Default for RealSystem {
+    fn default() -> Self {
+        RealSystem { command_timeout: Duration::from_millis(1000) }
+    }
+}
+
+impl SystemInfo for RealSystem {
+    fn read_file(&self, path: &str) -> io::Result<String> {
+        fs::read(path).map(|bytes| decode(&bytes))
+    }
+
+    fn env_var(&self, name: &str) -> Option<OsString> {
+        env::var_os(name)
+    }
+
+    fn read_dir(&self, path: &str) -> io::Result<Vec<String>> {
+        fs::read_dir(path)?
+            .map(|entry| Ok(entry?.file_name().to_string_lossy().into_owned()))
+            .collect()
+    }
+
+    fn command_output(&self, program: &str, args: &[&str]) -> io::Result<String> {
+        let mut command = Command::new(program);
+        command.args(args);
+
+        // A misbehaving program's escape codes would throw the layout off
+        run_with_timeout(command, self.command_timeout)
+            .map(|output| render::strip_ansi(&output))
+            .map_err(|err| io::Error::new(err.kind(), format!("{}: {}", program, err)))
+    }
+}
+
+// Files and output are read as bytes, so a stray one that isn't UTF-8 (e.g.
+// a Latin-1 é in os-release) costs a character instead of the whole text
+fn decode(bytes: &[u8]) -> String {
+    String::from_utf8_lossy(bytes).into_owned()
+}
+
+// Run a command and collect its standard output, killing it if it hasn't
+// finished within `timeout`. It gets a process group of its own, so whatever
+// it started in the background goes too and can't hold the output open.
+pub fn run_with_timeout(mut command: Command, timeout: Duration) -> io::Result<String> {
+    let mut child = command
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .process_group(0)
+        .spawn()?;
+
+    // Read on the side so a chatty command can't fill the pipe and stall
+    let mut stdout = child.stdout.take().expect("stdout is piped");
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        let mut output = Vec::new();
+        let _ = sender.send(stdout.read_to_end(&mut output).map(|_| output));
+    });
+
+    let deadline = Instant::now() + timeout;
+    let timed_out = |child: &mut Child| {
+        let _ = signal::killpg(Pid::from_raw(child.id() as i32), Signal::SIGKILL);
+        let _ = child.wait();
+        io::Error::new(io::ErrorKind::TimedOut, format!("timed out after {}ms", timeout.as_millis()))
+    };
+
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break status;
+        }
+        if Instant::now() >= deadline {
+            return Err(timed_out(&mut child));
+        }
+        thread::sleep(Duration::from_millis(5));
+    };
+
+    // The output ends once everything holding the pipe has closed it, which
+    // a leftover background process may never do
+    let output = match receiver.recv_timeout(deadline.saturating_duration_since(Instant::now())) {
+        Ok(output) => output?,
+        Err(RecvTimeoutError::Timeout) => return Err(timed_out(&mut child)),
+        Err(RecvTimeoutError::Disconnected) => return Err(io::Error::other("failed reading the output")),
+    };
+    if !status.success() {
+        return Err(io::Error::other(format!("exited with {}", status)));
+    }
+    Ok(decode(&output))
+}
+
+#[cfg(test)]
+pub mod mock {
+    use std::collections::HashMap;
+    use std::ffi::OsString;
+    use std::io;
+    use super::{decode, SystemInfo};
+
+    // A fake machine with only the files and variables a test adds
+    #[derive(Default)]
+    pub struct MockSystem {
+        files: HashMap<String, Vec<u8>>,
+        env: HashMap<String, OsString>,
+    }
+
+    impl MockSystem {
+        pub fn file(self, path: &str, contents: &str) -> Self {
+            self.file_bytes(path, contents.as_bytes())
+        }
+
+        // For files that aren't valid UTF-8
+        pub fn file_bytes(mut self, path: &str, contents: &[u8]) -> Self {
+            self.files.insert(path.to_string(), contents.to_vec());
+            self
+        }
+
+        pub fn env(mut self, name: &str, value: impl Into<OsString>) -> Self {
+            self.env.insert(name.to_string(), value.into());
+            self
+        }
+    }
+
+    impl SystemInfo for MockSystem {
+        fn read_file(&self, path: &str) -> io::Result<String> {
+            self.files.get(path)
+                .map(|contents| decode(contents))
+                .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, path.to_string()))
+        }
+
+        fn env_var(&self, name: &str) -> Option<OsString> {
+            self.env.get(name).cloned()
+        }
+
+        // Directories exist implicitly through the files added under them
+        fn read_dir(&self, path: &str) -> io::Result<Vec<String>> {
+            let prefix = format!("{}/", path.trim_end_matches('/'));
+            let mut entries: Vec<String> = self.files.keys()
+                .filter_map(|file| file.strip_prefix(&prefix))
+                .map(|rest| rest.split('/').next().unwrap_or(rest).to_string())
+                .collect();
+            entries.sort();
+            entries.dedup();
+
+            if entries.is_empty() {
+                return Err(io::Error::new(io::ErrorKind::NotFound, path.to_string()));
+            }
+            Ok(entries)
+        }
+
+        // Nothing is installed on the fake machine
+        fn command_output(&self, program: &str, _args: &[&str]) -> io::Result<String> {
+            Err(io::Error::new(io::ErrorKind::NotFound, program.to_string()))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn slow_command_times_out() {
+        let sys = RealSystem { command_timeout: Duration::from_millis(100) };
+        let start = Instant::now();
+
+        let err = sys.command_output("sleep", &["5"]).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::TimedOut);
+        assert!(start.elapsed() < Duration::from_secs(2));
+
+        // A background process keeps the output open after sh has exited, and
+        // is killed along with it
+        let pid_file = env::temp_dir().join(format!("risifetch-test-{}", std::process::id()));
+        let script = format!("sleep 5 & echo $! > {}; echo risi", pid_file.display());
+        let start = Instant::now();
+
+        let err = sys.command_output("sh", &["-c", &script]).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::TimedOut);
+        assert!(start.elapsed() < Duration::from_secs(2));
+
+        let pid: i32 = fs::read_to_string(&pid_file).unwrap().trim().parse().unwrap();
+        let _ = fs::remove_file(&pid_file);
+        thread::sleep(Duration::from_millis(50));
+        // Gone, or a zombie until init gets around to reaping it
+        let running = fs::read_to_string(format!("/proc/{}/stat", pid))
+            .is_ok_and(|stat| stat.rsplit_once(") ").is_some_and(|(_, rest)| !rest.starts_with('Z')));
+        assert!(!running, "sleep {} is still running", pid);
+    }
+
+    #[test]
+    fn command_within_timeout() {
+        let sys = RealSystem::default();
+
+        assert_eq!(sys.command_output("echo", &["risi"]).unwrap(), "risi\n");
+    }
+}
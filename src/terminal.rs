@@ -0,0 +1,128 @@
+use std::env;
+use std::io::{self, Write};
+use std::sync::OnceLock;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+use nix::libc;
+use nix::sys::signal::{self, SaFlags, SigAction, SigHandler, SigSet, Signal};
+use crate::colors;
+use crate::error::FetchError;
+use crate::system::SystemInfo;
+
+nix::ioctl_read_bad!(tiocgwinsz, libc::TIOCGWINSZ, libc::winsize);
+
+// Number of columns of the terminal on stdout, None when it isn't one.
+// $COLUMNS wins, so layouts can be checked without a real terminal.
+pub fn width() -> Option<usize> {
+    if let Some(columns) = env::var("COLUMNS").ok().and_then(|columns| columns.parse().ok()) {
+        return Some(columns).filter(|columns| *columns > 0);
+    }
+
+    let mut size = libc::winsize { ws_row: 0, ws_col: 0, ws_xpixel: 0, ws_ypixel: 0 };
+
+    // SAFETY: TIOCGWINSZ only writes a winsize into the pointer we hand it
+    unsafe { tiocgwinsz(libc::STDOUT_FILENO, &mut size) }.ok()?;
+
+    match size.ws_col {
+        0 => None,
+        columns => Some(columns as usize),
+    }
+}
+
+// Process names of terminal emulators, looked for among our ancestors
+const KNOWN_TERMINALS: [&str; 16] = [
+    "alacritty", "foot", "gnome-terminal-server", "kgx", "kitty", "konsole", "lxterminal",
+    "mate-terminal", "ptyxis-agent", "st", "terminator", "tilix", "urxvt", "wezterm-gui",
+    "xfce4-terminal", "xterm",
+];
+
+// Walking the process tree is not free, and the answer can't change
+static TERMINAL: OnceLock<Option<String>> = OnceLock::new();
+
+// The command name and parent of a process, from /proc/<pid>/stat
+fn process_info(sys: &dyn SystemInfo, pid: u32) -> Option<(String, u32)> {
+    let stat = sys.read_file(&format!("/proc/{}/stat", pid)).ok()?;
+
+    // The name is in parentheses and may itself contain spaces or ")"
+    let start = stat.find('(')?;
+    let end = stat.rfind(')')?;
+    let name = stat.get(start + 1..end)?.to_string();
+    let ppid = stat[end + 1..].split_whitespace().nth(1)?.parse().ok()?;
+
+    Some((name, ppid))
+}
+
+fn find_terminal(sys: &dyn SystemInfo, extra: &[String]) -> Option<String> {
+    let mut pid = std::os::unix::process::parent_id();
+
+    while pid > 1 {
+        let (name, ppid) = process_info(sys, pid)?;
+
+        if KNOWN_TERMINALS.contains(&name.as_str()) || extra.contains(&name) {
+            return Some(name);
+        }
+        pid = ppid;
+    }
+
+    None
+}
+
+// The terminal emulator risifetch runs in, `extra` adds names to look for
+pub fn get_terminal(sys: &dyn SystemInfo, extra: &[String]) -> Result<String, FetchError> {
+    TERMINAL.get_or_init(|| find_terminal(sys, extra))
+        .clone()
+        .ok_or_else(|| FetchError::NotAvailable("terminal".to_string()))
+}
+
+// The colors the terminal was found to support, "truecolor", "256", "16" or
+// "none", by the detection that hex colors are approximated for. Asked anew,
+// as --json and --field turn colors off whatever the terminal can do.
+pub fn get_color_support() -> Result<String, FetchError> {
+    Ok(colors::detect_mode(false).name().to_string())
+}
+
+// Set from the SIGINT handler to end --watch
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+extern "C" fn on_interrupt(_: libc::c_int) {
+    INTERRUPTED.store(true, Ordering::SeqCst);
+}
+
+// Redraw `draw`'s output every `interval` until Ctrl-C. Each frame overwrites
+// the previous one in place rather than clearing the screen, so it doesn't
+// flicker.
+pub fn watch(out: &mut impl Write,
+             interval: Duration,
+             mut draw: impl FnMut(&mut Vec<u8>) -> io::Result<()>) -> io::Result<()> {
+    let handler = SigAction::new(SigHandler::Handler(on_interrupt), SaFlags::empty(), SigSet::empty());
+    // SAFETY: the handler only stores to an atomic
+    unsafe { signal::sigaction(Signal::SIGINT, &handler) }
+        .map_err(io::Error::from)?;
+
+    // Clear once and hide the cursor while drawing
+    write!(out, "\x1b[2J\x1b[?25l")?;
+
+    let result = (|| {
+        while !INTERRUPTED.load(Ordering::SeqCst) {
+            let mut frame = Vec::new();
+            draw(&mut frame)?;
+
+            // Home the cursor, clear what's left of each line and anything
+            // below the frame
+            let frame = String::from_utf8_lossy(&frame).replace('\n', "\x1b[K\n");
+            write!(out, "\x1b[H{}\x1b[J", frame)?;
+            out.flush()?;
+
+            let deadline = Instant::now() + interval;
+            while Instant::now() < deadline && !INTERRUPTED.load(Ordering::SeqCst) {
+                thread::sleep(Duration::from_millis(50));
+            }
+        }
+        Ok(())
+    })();
+
+    // Give the cursor back even when drawing failed
+    write!(out, "\x1b[?25h")?;
+    result
+}
@@ -0,0 +1,133 @@
+use std::sync::OnceLock;
+use crate::colors::Color;
+use crate::config::ThemeColors;
+
+// The colors used to draw the info block next to the logo
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Theme {
+    pub name: &'static str,
+    // Field keys
+    pub label: Color,
+    // Field values and the "@" in the header
+    pub value: Color,
+    // Bullets and the user/host names
+    pub accent: Color,
+    // The line under the header
+    pub separator: Color,
+}
+
+pub static DEFAULT: Theme = Theme {
+    name: "default",
+    label: Color::Yellow,
+    value: Color::Cyan,
+    accent: Color::Yellow,
+    separator: Color::Cyan,
+};
+
+pub static MONO: Theme = Theme {
+    name: "mono",
+    label: Color::White,
+    value: Color::Gray,
+    accent: Color::White,
+    separator: Color::Gray,
+};
+
+pub static DRACULA: Theme = Theme {
+    name: "dracula",
+    label: Color::Rgb(255, 121, 198),
+    value: Color::Rgb(248, 248, 242),
+    accent: Color::Rgb(189, 147, 249),
+    separator: Color::Rgb(98, 114, 164),
+};
+
+pub static GRUVBOX: Theme = Theme {
+    name: "gruvbox",
+    label: Color::Rgb(254, 128, 25),
+    value: Color::Rgb(142, 192, 124),
+    accent: Color::Rgb(250, 189, 47),
+    separator: Color::Rgb(146, 131, 116),
+};
+
+pub static THEMES: [&Theme; 4] = [&DEFAULT, &MONO, &DRACULA, &GRUVBOX];
+
+// Colors that read well on both dark and light terminals
+const RANDOM_PALETTE: [Color; 6] = [
+    Color::Red,
+    Color::Green,
+    Color::Yellow,
+    Color::Blue,
+    Color::Magenta,
+    Color::Cyan,
+];
+
+static ACTIVE_THEME: OnceLock<Theme> = OnceLock::new();
+
+pub fn find(name: &str) -> Option<&'static Theme> {
+    THEMES.iter().copied().find(|theme| theme.name == name)
+}
+
+// Pick the theme for this run, only the first call has an effect
+pub fn set_active(theme: Theme) {
+    let _ = ACTIVE_THEME.set(theme);
+}
+
+pub fn active() -> &'static Theme {
+    ACTIVE_THEME.get_or_init(|| DEFAULT)
+}
+
+impl Theme {
+    // The theme with the colors set in the config's [colors] instead of its own
+    pub fn with_overrides(self, colors: &ThemeColors) -> Theme {
+        Theme {
+            label: colors.label.unwrap_or(self.label),
+            value: colors.value.unwrap_or(self.value),
+            accent: colors.accent.unwrap_or(self.accent),
+            separator: colors.separator.unwrap_or(self.separator),
+            ..self
+        }
+    }
+}
+
+// A theme with distinct label, value and accent colors picked from the seed
+pub fn random(seed: u64) -> Theme {
+    let mut palette = RANDOM_PALETTE.to_vec();
+    // xorshift, a zero state would never change
+    let mut state = seed | 1;
+    let mut pick = || {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        let index = (state % palette.len() as u64) as usize;
+        palette.remove(index)
+    };
+
+    let accent = pick();
+    let label = pick();
+    let value = pick();
+
+    Theme {
+        name: "random",
+        label,
+        value,
+        accent,
+        separator: value,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn overrides_win_over_the_base_theme() {
+        let colors = ThemeColors { value: Some(Color::Rgb(255, 255, 255)), accent: Some(Color::Red), ..ThemeColors::default() };
+        let theme = DRACULA.with_overrides(&colors);
+
+        assert_eq!(theme.value, Color::Rgb(255, 255, 255));
+        assert_eq!(theme.accent, Color::Red);
+        assert_eq!(theme.label, DRACULA.label);
+        assert_eq!(theme.separator, DRACULA.separator);
+        assert_eq!(theme.name, "dracula");
+        assert_eq!(MONO.with_overrides(&ThemeColors::default()), MONO);
+    }
+}
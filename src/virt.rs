@@ -0,0 +1,73 @@
+use crate::dmi;
+use crate::error::FetchError;
+use crate::system::SystemInfo;
+
+// Words in the DMI product name or vendor that give a hypervisor away, and
+// the name systemd-detect-virt uses for it
+const HYPERVISORS: [(&str, &str); 7] = [
+    ("kvm", "kvm"),
+    ("qemu", "qemu"),
+    ("vmware", "vmware"),
+    ("virtualbox", "oracle"),
+    ("hyper-v", "microsoft"),
+    ("virtual machine", "microsoft"),
+    ("xen", "xen"),
+];
+
+// Container runtimes named in PID 1's cgroups
+const CONTAINERS: [&str; 4] = ["docker", "lxc", "podman", "kubepods"];
+
+fn detect_container(sys: &dyn SystemInfo) -> Option<String> {
+    if sys.read_file("/.dockerenv").is_ok() {
+        return Some("docker".to_string());
+    }
+    if sys.read_file("/run/.containerenv").is_ok() {
+        return Some("podman".to_string());
+    }
+
+    let cgroup = sys.read_file("/proc/1/cgroup").ok()?;
+    CONTAINERS.iter()
+        .find(|runtime| cgroup.contains(*runtime))
+        .map(|runtime| runtime.to_string())
+}
+
+// 1 or 2 when running under the Windows Subsystem for Linux, whose kernels
+// say so in /proc/version, e.g. "5.15.90.1-microsoft-standard-WSL2"
+pub fn wsl_version(sys: &dyn SystemInfo) -> Option<u8> {
+    let version = sys.read_file("/proc/version").ok()?;
+
+    if version.contains("WSL2") {
+        Some(2)
+    } else if version.to_lowercase().contains("microsoft") || version.contains("WSL") {
+        Some(1)
+    } else {
+        None
+    }
+}
+
+fn detect_vm(sys: &dyn SystemInfo) -> Option<String> {
+    let dmi = ["product_name", "sys_vendor"].iter()
+        .filter_map(|name| dmi::read(sys, name))
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase();
+
+    HYPERVISORS.iter()
+        .find(|(word, _)| dmi.contains(word))
+        .map(|(_, name)| name.to_string())
+}
+
+// The VM or container risifetch runs in, e.g. "kvm" or "docker", named like
+// systemd-detect-virt does, which is asked first when it's installed
+pub fn get_virtualization(sys: &dyn SystemInfo) -> Result<String, FetchError> {
+    // It exits with an error and prints "none" on bare metal
+    let detected = sys.command_output("systemd-detect-virt", &[]).ok()
+        .map(|virt| virt.trim().to_string())
+        .filter(|virt| !virt.is_empty() && virt != "none");
+
+    detected
+        .or_else(|| detect_container(sys))
+        .or_else(|| wsl_version(sys).map(|_| "wsl".to_string()))
+        .or_else(|| detect_vm(sys))
+        .ok_or_else(|| FetchError::NotAvailable("virtualization".to_string()))
+}